@@ -40,7 +40,8 @@ pub fn lookup_game(game_id: &str, game_year: u32) -> Option<Game> {
     while let State::Row = c.next().unwrap() {
         let buf : String = c.read::<String>(2).unwrap();
         println!("{}", buf);
-        let g = serde_json::from_str(&buf).unwrap();
+        let mut g : Game = serde_json::from_str(&buf).unwrap();
+        g.upgrade_save();
         return Some(g);
     }
 
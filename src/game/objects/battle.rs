@@ -0,0 +1,127 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::tech::TechnologyId;
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum BattleTarget {
+    NoTarget,
+    Any,
+    Starbase,
+    Armed,
+    Bomber,
+    Unarmed,
+    FuelTransport,
+    Freighter
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum BattleTactic {
+    Disengage,
+    DisengageIfChallenged,
+    MinimizeDamage,
+    MaximizeNetDamage,
+    MaximizeDamageRatio,
+    MaximizeDamage
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum AttackWho {
+    Nobody,
+    Enemies,
+    EnemiesAndNeutrals,
+    Everyone,
+    Player(u8)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BattlePlan {
+    pub name: String,
+    pub primary_target: BattleTarget,
+    pub secondary_target: BattleTarget,
+    pub tactic: BattleTactic,
+    pub attack_who: AttackWho
+}
+
+impl BattlePlan {
+    pub fn construct(name: &str, primary_target: BattleTarget, secondary_target: BattleTarget, tactic: BattleTactic, attack_who: AttackWho) -> BattlePlan {
+        BattlePlan {
+            name: name.to_string(),
+            primary_target,
+            secondary_target,
+            tactic,
+            attack_who
+        }
+    }
+}
+
+pub const MAX_BATTLE_PLANS : usize = 16;
+
+/*
+    The plans every player starts with.  The first plan is the default for
+    new fleets and is used by starbases.
+*/
+pub fn construct_default_battle_plans() -> Vec<BattlePlan> {
+    vec![
+        BattlePlan::construct("Default", BattleTarget::Armed, BattleTarget::Any, BattleTactic::MaximizeDamageRatio, AttackWho::Enemies),
+        BattlePlan::construct("Kill Starbase", BattleTarget::Starbase, BattleTarget::Armed, BattleTactic::MaximizeDamageRatio, AttackWho::Enemies),
+        BattlePlan::construct("Return Fire", BattleTarget::Armed, BattleTarget::Any, BattleTactic::MaximizeDamageRatio, AttackWho::Nobody),
+        BattlePlan::construct("Sniper", BattleTarget::Unarmed, BattleTarget::Any, BattleTactic::MinimizeDamage, AttackWho::Enemies),
+        BattlePlan::construct("Chicken", BattleTarget::Any, BattleTarget::NoTarget, BattleTactic::Disengage, AttackWho::Enemies)
+    ]
+}
+
+/*
+    Everything a client needs to replay a battle.  Tokens are referred to
+    by their index in the token list.
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub enum BattleEvent {
+    Round(u32),
+    Place { token: u16, x: i8, y: i8 },
+    Move { token: u16, x: i8, y: i8 },
+    Fire { token: u16, target: u16, weapon: TechnologyId, shield_damage: u32, armor_damage: u32 },
+    Destroy { token: u16, ships: u16 }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecordedToken {
+    pub owner_id: u8,
+    pub design_id: u32,
+    pub design_name: String,
+    pub quantity: u16,
+    pub armor: u32,
+    pub damage: u32,
+    pub shields: u32
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BattleRecording {
+    pub id: u32,
+    pub location: SpaceCoordinate,
+    pub location_name: String,
+    pub participants: Vec<u8>,
+    // Players who watched the battle from nearby without taking part
+    pub observers: Vec<u8>,
+    pub tokens: Vec<RecordedToken>,
+    pub events: Vec<BattleEvent>
+}
@@ -20,40 +20,148 @@
  *  DEALINGS IN THE SOFTWARE.
  */
 use ::game::objects::tech::TechnologyId;
+use ::game::objects::tech::TechnologyCategory;
+use ::game::objects::tech::TechnologyCost;
 use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::tech::DOCK_CAPACITY_INFINITE_VALUE;
 use ::game::objects::universe::SpaceCoordinate;
 use ::game::objects::universe::Universe;
 use ::std::cmp::Ordering;
 
 pub const MAX_SHIP_DESIGNS : u8 = 16;
+pub const MAX_STARBASE_DESIGNS : u8 = 10;
+
+// Design ids are handed out per player from their own block of ids so that a
+// deleted design's id is never reused, and the owner can still be recovered
+// from the id alone.
+pub const MAX_DESIGN_IDS_PER_PLAYER : u32 = 65536;
 
 //std::fmt for strings
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct ShipSlot {
     pub tid: TechnologyId,
     pub amount: u8
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum CargoType {
+    Ironium,
+    Boranium,
+    Germanium,
+    Colonists
+}
+
+// Colonists are carried in kT; each kT holds 100 colonists
+pub const COLONISTS_PER_KT : u32 = 100;
+
+pub const GATLING_SWEEP_MULTIPLIER : u32 = 4;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Cargo {
+    pub ironium: u32,
+    pub boranium: u32,
+    pub germanium: u32,
+    pub colonists: u32
+}
+
+impl Cargo {
+    pub fn get(&self, cargo_type: CargoType) -> u32 {
+        match cargo_type {
+            CargoType::Ironium => self.ironium,
+            CargoType::Boranium => self.boranium,
+            CargoType::Germanium => self.germanium,
+            CargoType::Colonists => self.colonists
+        }
+    }
+
+    pub fn get_mut(&mut self, cargo_type: CargoType) -> &mut u32 {
+        match cargo_type {
+            CargoType::Ironium => &mut self.ironium,
+            CargoType::Boranium => &mut self.boranium,
+            CargoType::Germanium => &mut self.germanium,
+            CargoType::Colonists => &mut self.colonists
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.ironium + self.boranium + self.germanium + self.colonists
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone)]
+pub enum TransferAmount {
+    Amount(u32),
+    All,
+    FillPercent(u8)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CargoTransfer {
+    pub cargo_type: CargoType,
+    pub amount: TransferAmount
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub enum ShipOrderType {
+    #[default]
     NoTask,
-    Load,
-    Unload,
+    Load(Vec<CargoTransfer>),
+    Unload(Vec<CargoTransfer>),
     Colonize,
     Scrap,
-    Patrol,
+    // Intercept the nearest enemy fleet within the given range (0 for the
+    // fleet's scanner range) at the given warp
+    Patrol {
+        range: u16,
+        warp: u8
+    },
     RemoteMining,
     LayMines,
     Route,
     Merge,
-    Transfer
+    Transfer(u8),
+    Repair
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ShipOrder {
-    pub order_type: ShipOrderType,
-    pub amount: Option<u16>
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum WaypointTarget {
+    Planet(u32),
+    Fleet(u32),
+    Wormhole(u32),
+    MysteryTrader(u32),
+    #[default]
+    Space
+}
+
+/*
+    A fleet's orders are a list of waypoints.  The first waypoint is the
+    last one the fleet reached; the fleet travels toward the second one, and
+    carries out a waypoint's task once it gets there.
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Waypoint {
+    // Orders saved before waypoints only had a task (stored as order_type)
+    // for the fleet's current position; see Universe::place_legacy_waypoints
+    #[serde(default)]
+    pub target: WaypointTarget,
+    #[serde(default)]
+    pub location: SpaceCoordinate,
+    #[serde(default)]
+    pub warp: u8,
+    #[serde(default, alias = "order_type")]
+    pub task: ShipOrderType
+}
+
+impl Waypoint {
+    pub fn construct(target: WaypointTarget, location: SpaceCoordinate, warp: u8, task: ShipOrderType) -> Waypoint {
+        Waypoint {
+            target,
+            location,
+            warp,
+            task
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -62,34 +170,298 @@ pub struct ShipDesign {
     pub icon_index: u8,
     pub name: String,
     pub base_hull: TechnologyId,
+    #[serde(default)]
+    pub is_obsolete: bool,
     pub slots: Option<[Option<ShipSlot>; 16]>
 }
 
-#[derive(Serialize, Deserialize)]
+impl ShipDesign {
+    pub fn has_same_components(&self, other: &ShipDesign) -> bool {
+        self.base_hull == other.base_hull && self.slots == other.slots
+    }
+
+    pub fn is_starbase(&self) -> bool {
+        TECHNOLOGY_DETAILS[self.base_hull as usize].dock_capacity.is_some()
+    }
+
+    pub fn filled_slots(&self) -> Vec<&ShipSlot> {
+        match self.slots {
+            Some(ref slots) => slots.iter().filter_map(|s| s.as_ref()).collect(),
+            None => Vec::new()
+        }
+    }
+
+    /*
+        Checks the design against its hull's slot layout: every filled slot
+        must exist on the hull, hold a part the slot accepts, and hold no
+        more of it than the slot has room for.
+    */
+    pub fn fits_hull(&self) -> bool {
+        if self.base_hull.category() != TechnologyCategory::Hull {
+            return false;
+        }
+
+        let slots = match self.slots {
+            Some(ref slots) => slots,
+            None => return true
+        };
+
+        let hull_slots = TECHNOLOGY_DETAILS[self.base_hull as usize].slots.as_ref();
+        slots.iter().enumerate().all(|(i, s)| match (s, hull_slots.and_then(|h| h[i].as_ref())) {
+            (None, _) => true,
+            (Some(s), Some(h)) => s.amount > 0 && s.amount <= h.amount && h.slot_type.accepts(s.tid.category()),
+            (Some(_), None) => false
+        })
+    }
+
+    pub fn calculate_mass(&self) -> u32 {
+        let mut mass = TECHNOLOGY_DETAILS[self.base_hull as usize].mass.unwrap_or(0);
+
+        for s in self.filled_slots() {
+            mass += TECHNOLOGY_DETAILS[s.tid as usize].mass.unwrap_or(0) * (s.amount as u32);
+        }
+
+        mass
+    }
+
+    pub fn calculate_cargo_capacity(&self) -> u32 {
+        let mut cargo = TECHNOLOGY_DETAILS[self.base_hull as usize].cargo.unwrap_or(0);
+
+        for s in self.filled_slots() {
+            cargo += TECHNOLOGY_DETAILS[s.tid as usize].cargo.unwrap_or(0) * (s.amount as u32);
+        }
+
+        cargo
+    }
+
+    pub fn calculate_armor(&self) -> u32 {
+        let mut armor = TECHNOLOGY_DETAILS[self.base_hull as usize].armor.unwrap_or(0);
+
+        for s in self.filled_slots() {
+            armor += TECHNOLOGY_DETAILS[s.tid as usize].armor.unwrap_or(0) * (s.amount as u32);
+        }
+
+        armor
+    }
+
+    pub fn calculate_shields(&self) -> u32 {
+        let mut shields = 0;
+
+        for s in self.filled_slots() {
+            shields += TECHNOLOGY_DETAILS[s.tid as usize].shield_value.unwrap_or(0) * (s.amount as u32);
+        }
+
+        shields
+    }
+
+    // Every slot holding something that can fire in battle: beams, sappers,
+    // torpedoes and missiles all carry a power rating.
+    pub fn weapon_slots(&self) -> Vec<&ShipSlot> {
+        self.filled_slots().into_iter()
+            .filter(|s| TECHNOLOGY_DETAILS[s.tid as usize].power.is_some())
+            .collect()
+    }
+
+    pub fn is_armed(&self) -> bool {
+        !self.weapon_slots().is_empty()
+    }
+
+    /*
+        Mines swept per year.  Each beam sweeps its power times the square
+        of its range, and gatlings sweep several times that as they spray
+        every target in range.  Torpedoes, missiles and sappers do not sweep.
+    */
+    pub fn calculate_mine_sweep_rate(&self) -> u32 {
+        let mut rate = 0;
+
+        for s in self.weapon_slots() {
+            let tech = &TECHNOLOGY_DETAILS[s.tid as usize];
+            if tech.accuracy.is_some() || tech.hits_shields_only == Some(true) {
+                continue;
+            }
+
+            let range = tech.range.unwrap_or(0) as u32;
+            let mut swept = tech.power.unwrap_or(0) as u32 * range * range * s.amount as u32;
+            if tech.is_spread == Some(true) {
+                swept *= GATLING_SWEEP_MULTIPLIER;
+            }
+
+            rate += swept;
+        }
+
+        rate
+    }
+
+    pub fn scanner_range(&self) -> u16 {
+        let hull = TECHNOLOGY_DETAILS[self.base_hull as usize].basic_range.unwrap_or(0);
+
+        self.filled_slots().iter()
+            .filter_map(|s| TECHNOLOGY_DETAILS[s.tid as usize].basic_range)
+            .fold(hull, |best, r| best.max(r))
+    }
+
+    pub fn engine(&self) -> Option<TechnologyId> {
+        self.filled_slots().iter()
+            .find(|s| TECHNOLOGY_DETAILS[s.tid as usize].fuel_table.is_some())
+            .map(|s| s.tid)
+    }
+
+    /*
+        Ram scoop engines burn no fuel above warp 1; the fastest such warp is
+        the engine's free speed.
+    */
+    pub fn ram_scoop_free_warp(&self) -> Option<u8> {
+        let table = TECHNOLOGY_DETAILS[self.engine()? as usize].fuel_table?;
+
+        let free_warp = (2..table.len()).take_while(|w| table[*w] == 0).last();
+        free_warp.map(|w| w as u8)
+    }
+
+    pub fn count_slot_technology(&self, tid: TechnologyId) -> u32 {
+        self.filled_slots().iter()
+            .filter(|s| s.tid == tid)
+            .map(|s| s.amount as u32)
+            .sum()
+    }
+
+    pub fn regen_bonus_percent(&self) -> u32 {
+        TECHNOLOGY_DETAILS[self.base_hull as usize].regen_bonus_percent.unwrap_or(0) as u32
+    }
+
+    /*
+        Largest ship mass (in kT) that can be built at a starbase of this
+        design.  None means nothing can be built here; the original game
+        uses 5000 as a stand-in for unlimited capacity.
+    */
+    pub fn dock_capacity(&self) -> Option<u32> {
+        match TECHNOLOGY_DETAILS[self.base_hull as usize].dock_capacity {
+            Some(0) | None => None,
+            Some(DOCK_CAPACITY_INFINITE_VALUE) => Some(u32::MAX),
+            Some(c) => Some(c as u32)
+        }
+    }
+
+    pub fn calculate_cost(&self) -> TechnologyCost {
+        let hull = &TECHNOLOGY_DETAILS[self.base_hull as usize].cost;
+        let mut cost = TechnologyCost {
+            ironium: hull.ironium,
+            boranium: hull.boranium,
+            germanium: hull.germanium,
+            resources: hull.resources
+        };
+
+        for s in self.filled_slots() {
+            let c = &TECHNOLOGY_DETAILS[s.tid as usize].cost;
+            let amount = s.amount as u32;
+            cost.ironium += c.ironium * amount;
+            cost.boranium += c.boranium * amount;
+            cost.germanium += c.germanium * amount;
+            cost.resources += c.resources * amount;
+        }
+
+        cost
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct FleetMember {
     pub design_id: u32,
-    pub quantity: u16
+    pub quantity: u16,
+    // Armor damage carried by the ships of this stack
+    #[serde(default)]
+    pub damage: u32
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Fleet {
     pub id: u32,
+    #[serde(default)]
+    pub name: String,
     pub owner_id: Option<u8>,
     pub location: SpaceCoordinate,
-    pub heading: Option<SpaceCoordinate>,
-    pub warp: Option<u8>,
     pub current_fuel: u32,
     pub total_fuel_capacity: u32,
-    //pub total_cargo_capacity: Option<u32>,
-    // current damage level
-    // current fuel level
+    #[serde(default)]
+    pub cargo: Cargo,
+    #[serde(default)]
+    pub total_cargo_capacity: u32,
     // cloaking??
-    pub orders: Vec<ShipOrder>,
+    pub orders: Vec<Waypoint>,
     pub repeat_orders: bool,
-    pub members: Vec<FleetMember>
+    pub members: Vec<FleetMember>,
+    // Index into the owner's battle plans
+    #[serde(default)]
+    pub battle_plan: u8
 }
 
 impl Fleet {
+    pub fn next_waypoint(&self) -> Option<&Waypoint> {
+        self.orders.get(1)
+    }
+
+    /*
+        Called when the fleet reaches its next waypoint.  The waypoint it
+        left is dropped, or moved to the end of the list when the orders
+        repeat, so that the reached waypoint becomes the current position.
+    */
+    pub fn advance_waypoint(&mut self) {
+        if self.orders.len() < 2 {
+            return;
+        }
+
+        let departed = self.orders.remove(0);
+        if self.repeat_orders {
+            self.orders.push(departed);
+        }
+    }
+
+    pub fn ship_count(&self) -> u32 {
+        self.members.iter().map(|m| m.quantity as u32).sum()
+    }
+
+    /*
+        Adds ships to the fleet, grouping them with any ships of the same
+        design already present.
+    */
+    pub fn add_members(&mut self, design_id: u32, quantity: u16, damage: u32) {
+        match self.members.iter_mut().find(|m| m.design_id == design_id) {
+            Some(m) => {
+                m.quantity += quantity;
+                m.damage += damage;
+            },
+            None => self.members.push(FleetMember {
+                design_id,
+                quantity,
+                damage
+            })
+        }
+    }
+
+    pub fn is_damaged(&self) -> bool {
+        self.members.iter().any(|m| m.damage > 0)
+    }
+
+    /*
+        A fleet with a repair task stays at that waypoint until all of its
+        damage has been repaired.
+    */
+    pub fn is_holding_for_repair(&self) -> bool {
+        match self.orders[0].task {
+            ShipOrderType::Repair => self.is_damaged() && self.location.is_same_location(&self.orders[0].location),
+            _ => false
+        }
+    }
+
+    pub fn free_cargo_space(&self) -> u32 {
+        self.total_cargo_capacity.saturating_sub(self.cargo.total())
+    }
+
+    pub fn calculate_total_cargo_capacity(universe: &Universe, members: &[FleetMember]) -> u32 {
+        members.iter()
+            .filter_map(|m| universe.lookup_ship_design(m.design_id).map(|d| d.calculate_cargo_capacity() * m.quantity as u32))
+            .sum()
+    }
+
     pub fn calculate_total_fuel_capacity(universe: &Universe, members : &Vec<FleetMember>) -> u32 {
         let mut total_fuel = 0;
 
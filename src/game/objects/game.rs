@@ -23,17 +23,37 @@ use ::game::objects::universe::UniverseDensity;
 use ::game::objects::universe::UniverseSize;
 use ::game::objects::universe::Universe;
 use ::game::objects::player::Player;
+use ::game::objects::battle::BattleRecording;
 use uuid::Uuid;
+use rand;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 
 pub const STARTING_YEAR : u32 = 2400;
 
+// Format of stored games.  Games saved before the format was versioned
+// read as version 0 and are upgraded when they are loaded.
+pub const SAVE_VERSION : u32 = 1;
+
 #[derive(Serialize, Deserialize)]
 pub struct Game {
     pub id: String,
     pub name: String,
     pub year: u32,
     pub parameters: GameParameters,
-    pub universe: Universe
+    pub universe: Universe,
+
+    #[serde(default)]
+    pub save_version: u32,
+
+    // Every random outcome in a turn is drawn from a generator seeded with
+    // this value and the year, so replaying a turn gives the same result
+    #[serde(default)]
+    pub random_seed: u64,
+
+    // Battles fought during the turn that led to this year
+    #[serde(default)]
+    pub battles: Vec<BattleRecording>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -91,12 +111,41 @@ impl Game {
         let gid = Uuid::new_v4().to_string();
         let mut u = Universe::construct_random(&parameters.universe_size, &parameters.universe_density, &parameters.galaxy_clumping, &parameters.player_starting_distance);
         u.players = players;
+        u.record_original_habitats();
         Game {
             id: gid, 
             name: name,
             year: STARTING_YEAR,
             parameters: parameters,
-            universe: u
+            universe: u,
+            save_version: SAVE_VERSION,
+            random_seed: rand::random(),
+            battles: Vec::new()
         }
     }
+
+    /*
+        Brings a game loaded from storage up to the current save format.
+        Fields added since the game was saved take their defaults when it is
+        read; anything that has to be rewritten is handled here.
+    */
+    pub fn upgrade_save(&mut self) {
+        if self.save_version < 1 {
+            self.universe.renumber_legacy_designs();
+            self.universe.name_legacy_fleets();
+            self.universe.place_legacy_waypoints();
+            self.universe.size_legacy_cargo_holds();
+            self.universe.record_original_habitats();
+
+            // Games saved without a seed get one derived from their id, so
+            // that every load of the same save replays turns the same way
+            self.random_seed = self.id.bytes().fold(0, |seed, b| seed.wrapping_mul(31).wrapping_add(b as u64));
+        }
+
+        self.save_version = SAVE_VERSION;
+    }
+
+    pub fn turn_rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.random_seed ^ (self.year as u64))
+    }
 }
@@ -0,0 +1,199 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::predefined::messages::ORIGINAL_GAME_MESSAGES;
+
+/*
+    Message ids index into ORIGINAL_GAME_MESSAGES, so only the messages the
+    engine actually produces are named here.
+*/
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum MessageId {
+    InvasionMassacred = 0,
+    InvasionShotDownAndMassacred = 1,
+    InvasionRepelled = 3,
+    InvasionRepelledWithDefenses = 4,
+    MultiInvasionAllKilled = 5,
+    MultiAssaultNobodySurvived = 6,
+    PlanetInvaded = 7,
+    ColonizationRaceWon = 8,
+    ColonizationRaceLost = 9,
+    ColonizeSuccess = 10,
+    ColonizeStarterColony = 11,
+    InvasionSucceeded = 12,
+    InvasionTroopsDestroyed = 13,
+    FleetOutOfFuel = 39,
+    WaypointTargetDestroyed = 40,
+    WaypointTargetBehindPlanet = 41,
+    WaypointTargetOutOfRange = 42,
+    CargoLoaded = 43,
+    CargoBeamedFrom = 44,
+    CargoUnloaded = 45,
+    CargoBeamedTo = 46,
+    DefensesUpgraded = 59,
+    ColonizeNotInOrbit = 81,
+    ColonizeAlreadyPopulated = 82,
+    ColonizeNoColonists = 83,
+    ColonizeNoModule = 84,
+    ColonistsBeamedToUninhabited = 85,
+    ScrappedAtPlanet = 89,
+    ScrappedAtStarbase = 90,
+    ScrappedInSpace = 91,
+    ScrappedAtPlanetRecycled = 92,
+    ScrappedAtStarbaseRecycled = 93,
+    ArtifactFound = 94,
+    BombingKilled = 96,
+    BombingDestroyedOne = 97,
+    BombingDestroyed = 98,
+    BombingKilledDestroyedOne = 99,
+    BombingKilledDestroyed = 100,
+    BombingKilledStopped = 101,
+    BombingDestroyedOneStopped = 102,
+    BombingDestroyedStopped = 103,
+    BombingKilledDestroyedOneStopped = 104,
+    BombingKilledDestroyedStopped = 105,
+    BombedKilled = 106,
+    BombedDestroyedOne = 107,
+    BombedDestroyed = 108,
+    BombedKilledDestroyedOne = 109,
+    BombedKilledDestroyed = 110,
+    BombedKilledStopped = 111,
+    BombedDestroyedOneStopped = 112,
+    BombedDestroyedStopped = 113,
+    BombedKilledDestroyedOneStopped = 114,
+    BombedKilledDestroyedStopped = 115,
+    RemoteMiningNoModules = 117,
+    RemoteMiningInhabited = 118,
+    RemoteMiningDeepSpace = 119,
+    BattleObserved = 126,
+    CometSmall = 131,
+    CometMedium = 132,
+    CometLarge = 133,
+    CometHuge = 134,
+    CometSmallOwned = 135,
+    CometMediumOwned = 136,
+    CometLargeOwned = 137,
+    CometHugeOwned = 138,
+    FleetOutOfFuelWarpDecreased = 139,
+    BombingKilledAllEnemy = 143,
+    BombedKilledAll = 144,
+    BattleWonNoLosses = 155,
+    BattleLostAll = 156,
+    BattleWonWithLosses = 157,
+    BattleLostAllDestroyedSome = 158,
+    BattleUndecided = 159,
+    MultiBattleWonNoLosses = 164,
+    MultiBattleWonWithLosses = 165,
+    MultiBattleLostAll = 166,
+    MultiBattleLostAllDestroyedSome = 167,
+    MultiBattleUndecided = 168,
+    MinesSweptBySomeone = 189,
+    LayMinesNoDispensers = 190,
+    MysteryTraderArrived = 191,
+    FleetSweptMines = 193,
+    MinesDispersed = 194,
+    MinefieldIncreased = 195,
+    MinefieldStopped = 196,
+    MinefieldDamaged = 197,
+    MinefieldShipsDestroyed = 198,
+    MinefieldAnnihilated = 199,
+    OwnMinefieldStopped = 200,
+    OwnMinefieldDamaged = 201,
+    OwnMinefieldShipsDestroyed = 202,
+    OwnMinefieldAnnihilated = 203,
+    PacketNoMassDriver = 208,
+    PacketNoDestination = 209,
+    PacketLaunched = 210,
+    PacketCaught = 212,
+    PacketPartiallyCaught = 213,
+    PacketPartiallyCaughtDefensesLost = 214,
+    PacketBombarded = 215,
+    PacketBombardedDefensesLost = 216,
+    PacketAnnihilated = 217,
+    StargateNotAtSource = 221,
+    StargateNotAtDestination = 225,
+    StargateOutOfRange = 226,
+    StargateTooMassive = 227,
+    StargateDestinationBlocked = 228,
+    StargateSourceNotFriendly = 229,
+    StargateFleetLost = 230,
+    StargateFewShipsLost = 231,
+    StargateShipsLost = 232,
+    StargateManyShipsLost = 233,
+    StargateMostShipsLost = 234,
+    StargateUnloadedMinerals = 235,
+    StargateUnloadedColonists = 236,
+    StargateUnloadedColonistsAndMinerals = 237,
+    BalkyEngines = 241,
+    RamScoopFuelProduced = 242,
+    StarbaseSweptMines = 243,
+    MergeTargetNotFleet = 244,
+    MergeTargetNotOwned = 245,
+    FleetMerged = 246,
+    WaypointWormholeVanished = 247,
+    PacketNotEnoughMinerals = 251,
+    MysteryTraderRefused = 255,
+    MysteryTraderTechAdvances = 257,
+    MysteryTraderPart = 258,
+    MysteryTraderHull = 259,
+    MysteryTraderNothingNew = 261,
+    MysteryTraderDevice = 262,
+    WaypointMysteryTraderVanished = 263,
+    MysteryTraderRecovering = 270,
+    LoadFromForeignPlanet = 276,
+    RetroBombed = 291,
+    PacketBombardedNoDamage = 314,
+    StargateNoDestinationPlanet = 315,
+    FleetTransferHasColonists = 317,
+    FleetTransferNoDesignCapacity = 318,
+    FleetTransferRecipientNoDesignCapacity = 319,
+    FleetTransferred = 321,
+    FleetReceived = 322,
+    PacketIncoming = 325,
+    ColonistTransferToPlayerRefused = 329,
+    StargateColonistsOnBoard = 338,
+    PacketHitUninhabited = 371
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Message {
+    pub message_id: u16,
+    pub parameters: Vec<String>
+}
+
+impl Message {
+    pub fn construct(message_id: MessageId, parameters: Vec<String>) -> Message {
+        Message {
+            message_id: message_id as u16,
+            parameters
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = ORIGINAL_GAME_MESSAGES[self.message_id as usize].to_string();
+
+        for (i, p) in self.parameters.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", i), p);
+        }
+
+        text
+    }
+}
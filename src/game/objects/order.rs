@@ -0,0 +1,171 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::Waypoint;
+use ::game::objects::fleet::CargoTransfer;
+use ::game::objects::fleet::ShipDesign;
+use ::game::objects::player::PlayerRelation;
+use ::game::objects::battle::BattlePlan;
+
+/*
+    Orders a player submits with their turn.  They are carried out
+    immediately, before fleets move.
+*/
+#[derive(Serialize, Deserialize, Clone)]
+pub enum PlayerOrder {
+    SetWaypoints {
+        fleet_id: u32,
+        waypoints: Vec<Waypoint>,
+        repeat_orders: bool
+    },
+    MergeFleets {
+        fleet_id: u32,
+        target_fleet_id: u32
+    },
+    SplitFleet {
+        fleet_id: u32,
+        design_id: u32,
+        quantity: u16
+    },
+    TransferFleet {
+        fleet_id: u32,
+        to_player_id: u8
+    },
+    TransferCargo {
+        fleet_id: u32,
+        target_fleet_id: u32,
+        transfers: Vec<CargoTransfer>
+    },
+    SetRelation {
+        other_player_id: u8,
+        relation: PlayerRelation
+    },
+    SetBattlePlan {
+        index: u8,
+        plan: BattlePlan
+    },
+    DeleteBattlePlan {
+        index: u8
+    },
+    SetFleetBattlePlan {
+        fleet_id: u32,
+        index: u8
+    },
+    SetPacketDestination {
+        planet_id: u32,
+        destination_id: Option<u32>,
+        warp: u8
+    },
+    LaunchPacket {
+        planet_id: u32,
+        ironium: u16,
+        boranium: u16,
+        germanium: u16
+    },
+    AddShipDesign {
+        design: ShipDesign
+    },
+    QueueShipBuild {
+        planet_id: u32,
+        design_id: u32,
+        quantity: u16
+    },
+    DeleteShipDesign {
+        design_id: u32,
+        scrap_fleets: bool
+    },
+    CopyShipDesign {
+        design_id: u32
+    },
+    RenameShipDesign {
+        design_id: u32,
+        name: String
+    },
+    ObsoleteShipDesign {
+        design_id: u32,
+        is_obsolete: bool
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerOrders {
+    pub player_id: u8,
+    pub orders: Vec<PlayerOrder>
+}
+
+impl PlayerOrder {
+    pub fn execute(&self, universe: &mut Universe, player_id: u8) -> bool {
+        match *self {
+            PlayerOrder::SetWaypoints { fleet_id, ref waypoints, repeat_orders } => {
+                universe.set_fleet_waypoints(player_id, fleet_id, waypoints.clone(), repeat_orders)
+            },
+            PlayerOrder::MergeFleets { fleet_id, target_fleet_id } => {
+                universe.merge_fleets(player_id, fleet_id, target_fleet_id)
+            },
+            PlayerOrder::SplitFleet { fleet_id, design_id, quantity } => {
+                universe.split_fleet(player_id, fleet_id, design_id, quantity).is_some()
+            },
+            PlayerOrder::TransferFleet { fleet_id, to_player_id } => {
+                universe.transfer_fleet(player_id, fleet_id, to_player_id)
+            },
+            PlayerOrder::TransferCargo { fleet_id, target_fleet_id, ref transfers } => {
+                universe.transfer_cargo(player_id, fleet_id, target_fleet_id, transfers)
+            },
+            PlayerOrder::SetRelation { other_player_id, relation } => {
+                universe.set_player_relation(player_id, other_player_id, relation)
+            },
+            PlayerOrder::SetBattlePlan { index, ref plan } => {
+                universe.players[player_id as usize].set_battle_plan(index, plan.clone())
+            },
+            PlayerOrder::DeleteBattlePlan { index } => {
+                universe.delete_battle_plan(player_id, index)
+            },
+            PlayerOrder::SetFleetBattlePlan { fleet_id, index } => {
+                universe.set_fleet_battle_plan(player_id, fleet_id, index)
+            },
+            PlayerOrder::SetPacketDestination { planet_id, destination_id, warp } => {
+                universe.set_packet_destination(player_id, planet_id, destination_id, warp)
+            },
+            PlayerOrder::LaunchPacket { planet_id, ironium, boranium, germanium } => {
+                universe.launch_packet(player_id, planet_id, ironium, boranium, germanium)
+            },
+            PlayerOrder::AddShipDesign { ref design } => {
+                universe.add_ship_design(player_id, design.clone()).is_some()
+            },
+            PlayerOrder::QueueShipBuild { planet_id, design_id, quantity } => {
+                universe.queue_ship_build(player_id, planet_id, design_id, quantity)
+            },
+            PlayerOrder::DeleteShipDesign { design_id, scrap_fleets } => {
+                universe.delete_ship_design(player_id, design_id, scrap_fleets)
+            },
+            PlayerOrder::CopyShipDesign { design_id } => {
+                universe.copy_ship_design(player_id, design_id).is_some()
+            },
+            PlayerOrder::RenameShipDesign { design_id, ref name } => {
+                universe.rename_ship_design(player_id, design_id, name)
+            },
+            PlayerOrder::ObsoleteShipDesign { design_id, is_obsolete } => {
+                universe.set_ship_design_obsolete(player_id, design_id, is_obsolete)
+            }
+        }
+    }
+}
@@ -22,16 +22,44 @@
 use ::game::objects::universe::SpaceCoordinate;
 use ::game::objects::universe::MineralContents;
 use ::game::objects::player::Player;
+use ::game::objects::race::Race;
+use ::game::objects::race::PrimaryRacialTrait;
+use ::game::objects::race::LesserRacialTrait;
+use ::game::objects::race::LeftoverPointsOption;
+use ::game::objects::tech::TechnologyId;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
 
 
-#[derive(Serialize, Deserialize)]
+// Installations and kT of each surface mineral a homeworld starts with in
+// a normal-start game.  These have not been checked against a Stars! 2400
+// homeworld.
+pub const HOMEWORLD_STARTING_MINES : u16 = 10;
+pub const HOMEWORLD_STARTING_FACTORIES : u16 = 10;
+pub const HOMEWORLD_STARTING_DEFENSES : u16 = 10;
+pub const HOMEWORLD_STARTING_SURFACE_MINERALS : u16 = 300;
+pub const ACCELERATED_PLAY_INSTALLATION_MULTIPLIER : u16 = 2;
+
+// Mining lowers a mineral's concentration by one point for every this many
+// kT extracted; homeworlds never fall below the minimum concentration
+pub const MINED_KT_PER_CONCENTRATION_POINT : u16 = 125;
+pub const MINIMUM_CONCENTRATION : u16 = 1;
+pub const MINIMUM_HOMEWORLD_CONCENTRATION : u16 = 30;
+
+// Leftover advantage point exchange rates from the race wizard
+pub const LEFTOVER_POINTS_PER_MINE : u16 = 2;
+pub const LEFTOVER_POINTS_PER_FACTORY : u16 = 5;
+pub const LEFTOVER_POINTS_PER_DEFENSE : u16 = 10;
+pub const LEFTOVER_POINTS_PER_CONCENTRATION : u16 = 3;
+pub const SURFACE_MINERALS_PER_LEFTOVER_POINT : u16 = 10;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct HabitatLevel {
     pub temperature: u8,
     pub gravity: u8,
     pub radiation: u8
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, PartialOrd)]
 pub enum DefenseQuality {
     SDI,
     Missile,
@@ -40,6 +68,34 @@ pub enum DefenseQuality {
     Neutron
 }
 
+impl DefenseQuality {
+    pub fn technology(&self) -> TechnologyId {
+        match *self {
+            DefenseQuality::SDI => TechnologyId::SDI,
+            DefenseQuality::Missile => TechnologyId::MissileBattery,
+            DefenseQuality::Laser => TechnologyId::LaserBattery,
+            DefenseQuality::Planet => TechnologyId::PlanetaryShield,
+            DefenseQuality::Neutron => TechnologyId::NeutronShield
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            DefenseQuality::SDI => "SDI",
+            DefenseQuality::Missile => "Missile Battery",
+            DefenseQuality::Laser => "Laser Battery",
+            DefenseQuality::Planet => "Planetary Shield",
+            DefenseQuality::Neutron => "Neutron Shield"
+        }
+    }
+}
+
+// Smart bombs slip past half of a planet's defense coverage
+pub const SMART_BOMB_COVERAGE_FACTOR : f64 = 0.5;
+
+// Defenses are three quarters as effective against invading troops
+pub const INVASION_COVERAGE_FACTOR : f64 = 0.75;
+
 pub enum PlanetDesigns {
     Mines,
     Factories,
@@ -113,7 +169,7 @@ pub fn radiation_display_level_to_habitat_level(display_level : &str) -> u8 {
 pub struct BuildItem {
     pub quantity: u16,
     pub percent_complete: u8,
-    pub design_id: u16,
+    pub design_id: u32,
     pub required_resources: u32,
     pub required_minerals: MineralContents,
     pub estimated_completion_year: Option<u32>
@@ -160,13 +216,24 @@ pub struct Planet {
     pub has_ever_been_colonized: bool,
     pub mineral_concentration: MineralContents,
     pub on_surface: MineralContents,
-    pub habitat: HabitatLevel
+    pub habitat: HabitatLevel,
+
+    // Habitat the planet started the game with, before any terraforming;
+    // recorded once the universe has been generated
+    #[serde(default)]
+    pub original_habitat: Option<HabitatLevel>,
+
+    // kT mined of each mineral since its concentration last dropped
+    #[serde(default)]
+    pub mining_depletion: MineralContents
 }
 
 impl Planet {
     pub fn set_homeworld(&mut self, player: &mut Player) {
         player.homeworld_id = self.id;
         self.is_homeworld = true;
+        self.owner_id = Some(player.id);
+        self.has_ever_been_colonized = true;
 
         // The ideal value for habitat stays random for an immunity
         if !player.race.gravity_immune {
@@ -182,6 +249,202 @@ impl Planet {
         }
     }
 
+    /*
+        Gives a homeworld its starting installations and surface minerals.
+
+        Accelerated play doubles the mines and factories, Alternate Reality
+        races live in their starbases and start without mines or factories,
+        and Ultimate Recycling races keep an extra stockpile of minerals.
+        Scenario maps that already define surface minerals keep the larger of
+        the two amounts.
+    */
+    pub fn setup_homeworld_installations(&mut self, race: &Race, accelerated_play: bool) {
+        let multiplier = if accelerated_play { ACCELERATED_PLAY_INSTALLATION_MULTIPLIER } else { 1 };
+
+        match race.primary_racial_trait {
+            PrimaryRacialTrait::AlternateReality => {
+                self.mines = 0;
+                self.factories = 0;
+            },
+            _ => {
+                self.mines = HOMEWORLD_STARTING_MINES * multiplier;
+                self.factories = HOMEWORLD_STARTING_FACTORIES * multiplier;
+            }
+        }
+
+        self.defenses = HOMEWORLD_STARTING_DEFENSES;
+
+        let mut surface = HOMEWORLD_STARTING_SURFACE_MINERALS * multiplier;
+        if race.lesser_racial_traits.contains(&LesserRacialTrait::UltimateRecycling) {
+            surface += HOMEWORLD_STARTING_SURFACE_MINERALS / 2;
+        }
+
+        self.on_surface.ironium = self.on_surface.ironium.max(surface);
+        self.on_surface.boranium = self.on_surface.boranium.max(surface);
+        self.on_surface.germanium = self.on_surface.germanium.max(surface);
+
+        self.spend_leftover_points(race);
+    }
+
+    /*
+        Leftover points are converted at the race wizard's rates.  Mineral
+        concentration points always go to the homeworld's poorest mineral.
+    */
+    pub fn spend_leftover_points(&mut self, race: &Race) {
+        let points = race.spendable_leftover_points();
+
+        match race.leftover_points {
+            LeftoverPointsOption::SurfaceMinerals => {
+                let amount = points * SURFACE_MINERALS_PER_LEFTOVER_POINT / 3;
+                self.on_surface.ironium += amount;
+                self.on_surface.boranium += amount;
+                self.on_surface.germanium += amount;
+            },
+            LeftoverPointsOption::Mines => {
+                self.mines += points / LEFTOVER_POINTS_PER_MINE;
+            },
+            LeftoverPointsOption::Factories => {
+                self.factories += points / LEFTOVER_POINTS_PER_FACTORY;
+            },
+            LeftoverPointsOption::Defenses => {
+                self.defenses += points / LEFTOVER_POINTS_PER_DEFENSE;
+            },
+            LeftoverPointsOption::MineralConcentration => {
+                for _ in 0..(points / LEFTOVER_POINTS_PER_CONCENTRATION) {
+                    let c = &mut self.mineral_concentration;
+                    if c.ironium <= c.boranium && c.ironium <= c.germanium {
+                        c.ironium += 1;
+                    }
+                    else if c.boranium <= c.germanium {
+                        c.boranium += 1;
+                    }
+                    else {
+                        c.germanium += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /*
+        Mines the planet with the given number of mine equivalents.  Each
+        mine extracts concentration / 100 kT of every mineral per year, and
+        the extracted minerals wear the concentrations down.  Returns the kT
+        mined of ironium, boranium and germanium.
+    */
+    pub fn extract_minerals(&mut self, mines: u32) -> (u32, u32, u32) {
+        let floor = if self.is_homeworld { MINIMUM_HOMEWORLD_CONCENTRATION } else { MINIMUM_CONCENTRATION };
+
+        let extract = |concentration: &mut u16, depletion: &mut u16| -> u32 {
+            let amount = mines * (*concentration as u32) / 100;
+            let total = *depletion as u32 + amount;
+
+            let drop = (total / MINED_KT_PER_CONCENTRATION_POINT as u32).min(u16::MAX as u32) as u16;
+            *depletion = (total % MINED_KT_PER_CONCENTRATION_POINT as u32) as u16;
+            *concentration = concentration.saturating_sub(drop).max(floor.min(*concentration));
+            amount
+        };
+
+        let ironium = extract(&mut self.mineral_concentration.ironium, &mut self.mining_depletion.ironium);
+        let boranium = extract(&mut self.mineral_concentration.boranium, &mut self.mining_depletion.boranium);
+        let germanium = extract(&mut self.mineral_concentration.germanium, &mut self.mining_depletion.germanium);
+
+        self.on_surface.add_saturating(ironium, boranium, germanium);
+        (ironium, boranium, germanium)
+    }
+
+    /*
+        Share of incoming bombs stopped by the planet's defenses.  Each
+        defense covers a small share of the planet on its own:
+
+            coverage = 1 - (1 - per_defense)^defenses
+    */
+    pub fn defense_coverage(&self) -> f64 {
+        let per_defense = match self.defense_quality {
+            Some(ref q) => TECHNOLOGY_DETAILS[q.technology() as usize].coverage.unwrap_or(0.0) as f64,
+            None => return 0.0
+        };
+
+        1.0 - (1.0 - per_defense).powi(self.defenses as i32)
+    }
+
+    pub fn smart_bomb_defense_coverage(&self) -> f64 {
+        self.defense_coverage() * SMART_BOMB_COVERAGE_FACTOR
+    }
+
+    pub fn invasion_defense_coverage(&self) -> f64 {
+        self.defense_coverage() * INVASION_COVERAGE_FACTOR
+    }
+
+    /*
+        Moves each habitat value the given number of clicks back toward its
+        value before terraforming.  Returns the percent of the terraforming
+        that was undone.
+    */
+    pub fn undo_terraforming(&mut self, clicks: u32) -> u32 {
+        let original = match self.original_habitat {
+            Some(ref h) => h.clone(),
+            None => return 0
+        };
+
+        let distance = |from: u8, to: u8| -> u32 { (from as i32 - to as i32).unsigned_abs() };
+        let total = distance(self.habitat.gravity, original.gravity)
+            + distance(self.habitat.temperature, original.temperature)
+            + distance(self.habitat.radiation, original.radiation);
+        if total == 0 {
+            return 0;
+        }
+
+        let step = |value: &mut u8, target: u8| {
+            let moved = distance(*value, target).min(clicks) as u8;
+            if *value > target { *value -= moved } else { *value += moved }
+        };
+        step(&mut self.habitat.gravity, original.gravity);
+        step(&mut self.habitat.temperature, original.temperature);
+        step(&mut self.habitat.radiation, original.radiation);
+
+        let remaining = distance(self.habitat.gravity, original.gravity)
+            + distance(self.habitat.temperature, original.temperature)
+            + distance(self.habitat.radiation, original.radiation);
+
+        (total - remaining) * 100 / total
+    }
+
+    /*
+        Takes destroyed installations from defenses, factories and mines in
+        proportion to how many of each the planet has.
+    */
+    pub fn destroy_installations(&mut self, count: u32) -> u32 {
+        let total = self.defenses as u32 + self.factories as u32 + self.mines as u32;
+        let count = count.min(total);
+        if count == 0 {
+            return 0;
+        }
+
+        let defenses = (self.defenses as u32 * count / total) as u16;
+        let factories = (self.factories as u32 * count / total) as u16;
+        let mines = (self.mines as u32 * count / total) as u16;
+        self.defenses -= defenses;
+        self.factories -= factories;
+        self.mines -= mines;
+
+        let mut left = count - defenses as u32 - factories as u32 - mines as u32;
+        while left > 0 {
+            if self.defenses >= self.factories && self.defenses >= self.mines {
+                self.defenses -= 1;
+            }
+            else if self.factories >= self.mines {
+                self.factories -= 1;
+            }
+            else {
+                self.mines -= 1;
+            }
+            left -= 1;
+        }
+
+        count
+    }
+
     pub fn construct_with_defaults(name: &str, id: u32, x: u16, y: u16) -> Planet {
         Planet {
             id: id,
@@ -217,8 +480,122 @@ impl Planet {
                 gravity: 0,
                 temperature: 0,
                 radiation: 0
+            },
+            original_habitat: None,
+            mining_depletion: MineralContents {
+                ironium: 0,
+                boranium: 0,
+                germanium: 0
             }
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::objects::race::MAX_SPENDABLE_LEFTOVER_POINTS;
+
+    fn homeworld(race: &Race, accelerated_play: bool) -> Planet {
+        let mut p = Planet::construct_with_defaults("Home", 1, 0, 0);
+        p.is_homeworld = true;
+        p.setup_homeworld_installations(race, accelerated_play);
+        p
+    }
+
+    fn economy(p: &Planet) -> (u16, u16, u16, u16) {
+        assert_eq!(p.on_surface.ironium, p.on_surface.boranium);
+        assert_eq!(p.on_surface.ironium, p.on_surface.germanium);
+        (p.mines, p.factories, p.defenses, p.on_surface.ironium)
+    }
+
+    #[test]
+    fn humanoid_homeworld_gets_the_starting_installations() {
+        let p = homeworld(&create_humanoid(), false);
+        assert_eq!(economy(&p), (HOMEWORLD_STARTING_MINES, HOMEWORLD_STARTING_FACTORIES,
+            HOMEWORLD_STARTING_DEFENSES, HOMEWORLD_STARTING_SURFACE_MINERALS));
+    }
+
+    #[test]
+    fn accelerated_play_doubles_mines_factories_and_minerals_but_not_defenses() {
+        let normal = economy(&homeworld(&create_humanoid(), false));
+        let accelerated = economy(&homeworld(&create_humanoid(), true));
+
+        assert_eq!(accelerated, (normal.0 * 2, normal.1 * 2, normal.2, normal.3 * 2));
+    }
+
+    #[test]
+    fn alternate_reality_homeworld_has_no_mines_or_factories() {
+        let mut race = create_humanoid();
+        race.primary_racial_trait = PrimaryRacialTrait::AlternateReality;
+
+        let p = homeworld(&race, false);
+        assert_eq!(economy(&p), (0, 0, 10, 300));
+    }
+
+    #[test]
+    fn ultimate_recycling_homeworld_has_extra_minerals() {
+        let mut race = create_humanoid();
+        race.lesser_racial_traits.push(LesserRacialTrait::UltimateRecycling);
+
+        let p = homeworld(&race, false);
+        assert_eq!(economy(&p), (10, 10, 10, 450));
+    }
+
+    #[test]
+    fn scenario_surface_minerals_are_kept() {
+        let mut p = Planet::construct_with_defaults("Stove Top", 14, 237, 127);
+        p.on_surface.ironium = 424;
+        p.on_surface.boranium = 477;
+        p.on_surface.germanium = 622;
+        p.setup_homeworld_installations(&create_humanoid(), false);
+
+        assert_eq!((p.on_surface.ironium, p.on_surface.boranium, p.on_surface.germanium), (424, 477, 622));
+    }
+
+    #[test]
+    fn leftover_points_buy_the_chosen_option() {
+        let mut race = create_humanoid();
+        race.advantage_points = 50;
+
+        race.leftover_points = LeftoverPointsOption::SurfaceMinerals;
+        assert_eq!(economy(&homeworld(&race, false)), (10, 10, 10, 466));
+
+        race.leftover_points = LeftoverPointsOption::Mines;
+        assert_eq!(economy(&homeworld(&race, false)), (35, 10, 10, 300));
+
+        race.leftover_points = LeftoverPointsOption::Factories;
+        assert_eq!(economy(&homeworld(&race, false)), (10, 20, 10, 300));
+
+        race.leftover_points = LeftoverPointsOption::Defenses;
+        assert_eq!(economy(&homeworld(&race, false)), (10, 10, 15, 300));
+    }
+
+    #[test]
+    fn leftover_concentration_points_raise_the_poorest_mineral() {
+        let mut race = create_humanoid();
+        race.advantage_points = 50;
+        race.leftover_points = LeftoverPointsOption::MineralConcentration;
+
+        let mut p = Planet::construct_with_defaults("Home", 1, 0, 0);
+        p.mineral_concentration.ironium = 70;
+        p.mineral_concentration.boranium = 84;
+        p.mineral_concentration.germanium = 25;
+        p.setup_homeworld_installations(&race, false);
+
+        assert_eq!((p.mineral_concentration.ironium, p.mineral_concentration.boranium, p.mineral_concentration.germanium), (70, 84, 41));
+    }
+
+    #[test]
+    fn unspendable_leftover_points_are_capped() {
+        let mut race = create_humanoid();
+        race.leftover_points = LeftoverPointsOption::Mines;
+
+        race.advantage_points = -20;
+        assert_eq!(economy(&homeworld(&race, false)).0, 10);
+
+        race.advantage_points = MAX_SPENDABLE_LEFTOVER_POINTS + 40;
+        assert_eq!(economy(&homeworld(&race, false)).0, 35);
+    }
+}
@@ -24,10 +24,26 @@ use ::game::objects::tech::ResearchField;
 use ::game::objects::tech::TechnologyId;
 use ::game::objects::tech::Technology;
 use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::tech::MAX_TECH_LEVEL;
 use ::game::objects::fleet::ShipDesign;
 use ::game::objects::fleet::MAX_SHIP_DESIGNS;
+use ::game::objects::fleet::MAX_STARBASE_DESIGNS;
+use ::game::objects::fleet::MAX_DESIGN_IDS_PER_PLAYER;
+use ::game::objects::message::Message;
+use ::game::objects::message::MessageId;
+use ::game::objects::battle::BattlePlan;
+use ::game::objects::battle::MAX_BATTLE_PLANS;
+use ::game::objects::battle::construct_default_battle_plans;
+use ::game::objects::planet::DefenseQuality;
 
 
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum PlayerRelation {
+    Friend,
+    Neutral,
+    Enemy
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Player {
     pub id: u8,
@@ -41,7 +57,32 @@ pub struct Player {
     pub next_research_field: ResearchField,
     pub available_tech_ids: Vec<TechnologyId>,
     pub learned_tech_ids: Vec<TechnologyId>,
-    pub ship_designs: [Option<ShipDesign>; MAX_SHIP_DESIGNS as usize]
+
+    // Parts, hulls and devices bought from the Mystery Trader
+    #[serde(default)]
+    pub trader_items: Vec<TechnologyId>,
+    pub ship_designs: [Option<ShipDesign>; MAX_SHIP_DESIGNS as usize],
+    #[serde(default)]
+    pub starbase_designs: [Option<ShipDesign>; MAX_STARBASE_DESIGNS as usize],
+
+    // Deleted designs that are still flown by orphaned fleets
+    #[serde(default)]
+    pub retired_designs: Vec<ShipDesign>,
+    #[serde(default)]
+    pub next_design_serial: u32,
+
+    // Messages generated for this player during the last turn
+    #[serde(default)]
+    pub messages: Vec<Message>,
+
+    // How this player regards the others, indexed by player id.  Players
+    // past the end of the list are enemies.
+    #[serde(default)]
+    pub relations: Vec<PlayerRelation>,
+
+    // Fleets refer to these by index; the first one can not be removed
+    #[serde(default = "construct_default_battle_plans")]
+    pub battle_plans: Vec<BattlePlan>
 }
 
 impl Player {
@@ -64,10 +105,73 @@ impl Player {
 
             learned_tech_ids: learned_techs,
             available_tech_ids: available_techs,
+            trader_items: Vec::new(),
             ship_designs: [
                 None, None, None, None, None, None, None, None,
-                None, None, None, None, None, None, None, None ]
+                None, None, None, None, None, None, None, None ],
+            starbase_designs: [
+                None, None, None, None, None,
+                None, None, None, None, None ],
+            retired_designs: Vec::new(),
+            next_design_serial: 0,
+            messages: Vec::new(),
+            relations: Vec::new(),
+            battle_plans: construct_default_battle_plans()
+        }
+    }
+
+    pub fn add_message(&mut self, message_id: MessageId, parameters: Vec<String>) {
+        self.messages.push(Message::construct(message_id, parameters));
+    }
+
+    pub fn relation_to(&self, player_id: u8) -> PlayerRelation {
+        if player_id == self.id {
+            return PlayerRelation::Friend;
+        }
+
+        match self.relations.get(player_id as usize) {
+            Some(r) => *r,
+            None => PlayerRelation::Enemy
+        }
+    }
+
+    pub fn set_relation(&mut self, player_id: u8, relation: PlayerRelation) {
+        if self.relations.len() <= player_id as usize {
+            self.relations.resize(player_id as usize + 1, PlayerRelation::Enemy);
+        }
+
+        self.relations[player_id as usize] = relation;
+    }
+
+    /*
+        The plan at the index, falling back to the player's first plan and
+        then to the standard default plan.
+    */
+    pub fn battle_plan(&self, index: u8) -> BattlePlan {
+        match self.battle_plans.get(index as usize).or_else(|| self.battle_plans.first()) {
+            Some(plan) => plan.clone(),
+            None => construct_default_battle_plans().remove(0)
+        }
+    }
+
+    /*
+        Replaces the plan at the index, or adds a new plan when the index is
+        one past the last plan.
+    */
+    pub fn set_battle_plan(&mut self, index: u8, plan: BattlePlan) -> bool {
+        let index = index as usize;
 
+        if index < self.battle_plans.len() {
+            self.battle_plans[index] = plan;
+            true
+        }
+        else if index == self.battle_plans.len() && index < MAX_BATTLE_PLANS {
+            self.battle_plans.push(plan);
+            true
+        }
+        else {
+            error!("Player {} cannot set battle plan {}", self.id, index);
+            false
         }
     }
 
@@ -81,23 +185,20 @@ impl Player {
         return None;
     }
 
-    fn get_next_available_ship_design_id(&self) -> Option<u32> {
-        let i = self.get_next_available_ship_design_slot();
-        match i {
-            Some(index) => {
-                return Some((self.id as u32 * MAX_SHIP_DESIGNS as u32) + index as u32);
-            }
-            None => {
-                return None;
-            }
-        }
+    fn get_next_available_starbase_design_slot(&self) -> Option<u8> {
+        (0..MAX_STARBASE_DESIGNS).find(|i| self.starbase_designs[*i as usize].is_none())
+    }
+
+    fn get_next_design_id(&mut self) -> u32 {
+        let id = (self.id as u32 * MAX_DESIGN_IDS_PER_PLAYER) + self.next_design_serial;
+        self.next_design_serial += 1;
+        id
     }
 
     pub fn add_ship_design(&mut self, mut d: ShipDesign) -> u8 {
         match self.get_next_available_ship_design_slot() {
             Some(index) => {
-                let id = self.get_next_available_ship_design_id().unwrap();
-                d.id = id;
+                d.id = self.get_next_design_id();
                 self.ship_designs[index as usize] = Some(d);
                 return index;
             }
@@ -108,6 +209,147 @@ impl Player {
         }
     }
 
+    pub fn add_starbase_design(&mut self, mut d: ShipDesign) -> u8 {
+        match self.get_next_available_starbase_design_slot() {
+            Some(index) => {
+                d.id = self.get_next_design_id();
+                self.starbase_designs[index as usize] = Some(d);
+                index
+            }
+            None => {
+                error!("Tried to add starbase design but no slots remain.");
+                MAX_STARBASE_DESIGNS + 1
+            }
+        }
+    }
+
+    /*
+        Gives every design a fresh id from the player's serial numbers and
+        returns the (old, new) id pairs so that references to the designs
+        can be updated.  Used to upgrade saves from before ids were
+        assigned per player.
+    */
+    pub fn renumber_designs(&mut self) -> Vec<(u32, u32)> {
+        let mut renumbered = Vec::new();
+
+        for i in 0..self.ship_designs.len() {
+            let old_id = match self.ship_designs[i] {
+                Some(ref d) => d.id,
+                None => continue
+            };
+
+            let id = self.get_next_design_id();
+            if let Some(ref mut d) = self.ship_designs[i] {
+                d.id = id;
+            }
+            renumbered.push((old_id, id));
+        }
+
+        renumbered
+    }
+
+    pub fn lookup_design(&self, design_id: u32) -> Option<&ShipDesign> {
+        self.ship_designs.iter()
+            .chain(self.starbase_designs.iter())
+            .filter_map(|d| d.as_ref())
+            .chain(self.retired_designs.iter())
+            .find(|d| d.id == design_id)
+    }
+
+    // Like lookup_design, but only designs the player can still build
+    pub fn lookup_active_design(&self, design_id: u32) -> Option<&ShipDesign> {
+        self.ship_designs.iter()
+            .chain(self.starbase_designs.iter())
+            .filter_map(|d| d.as_ref())
+            .find(|d| d.id == design_id)
+    }
+
+    fn lookup_design_mut(&mut self, design_id: u32) -> Option<&mut ShipDesign> {
+        self.ship_designs.iter_mut()
+            .chain(self.starbase_designs.iter_mut())
+            .filter_map(|d| d.as_mut())
+            .find(|d| d.id == design_id)
+    }
+
+    pub fn find_matching_design(&self, design: &ShipDesign) -> Option<u32> {
+        self.ship_designs.iter()
+            .filter_map(|d| d.as_ref())
+            .find(|d| d.has_same_components(design))
+            .map(|d| d.id)
+    }
+
+    pub fn free_ship_design_slots(&self) -> usize {
+        self.ship_designs.iter().filter(|d| d.is_none()).count()
+    }
+
+    pub fn has_ship_design_capacity(&self) -> bool {
+        self.get_next_available_ship_design_slot().is_some()
+    }
+
+    /*
+        Copies an existing design into the next free slot of the same kind
+        (ship or starbase).  Returns the slot of the copy, or None if the
+        design does not exist or there is no room left.
+    */
+    pub fn copy_ship_design(&mut self, design_id: u32) -> Option<u8> {
+        let mut copy = match self.lookup_design_mut(design_id) {
+            Some(d) => d.clone(),
+            None => {
+                return None;
+            }
+        };
+        copy.is_obsolete = false;
+
+        if copy.is_starbase() {
+            self.get_next_available_starbase_design_slot()?;
+            Some(self.add_starbase_design(copy))
+        }
+        else {
+            self.get_next_available_ship_design_slot()?;
+            Some(self.add_ship_design(copy))
+        }
+    }
+
+    pub fn rename_ship_design(&mut self, design_id: u32, name: &str) -> bool {
+        match self.lookup_design_mut(design_id) {
+            Some(d) => {
+                d.name = name.to_string();
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn set_ship_design_obsolete(&mut self, design_id: u32, is_obsolete: bool) -> bool {
+        match self.lookup_design_mut(design_id) {
+            Some(d) => {
+                d.is_obsolete = is_obsolete;
+                true
+            }
+            None => false
+        }
+    }
+
+    /*
+        Frees the slot holding the design and hands the design back to the
+        caller.  Fleets flying the design are the universe's concern; see
+        Universe::delete_ship_design.
+    */
+    pub fn remove_ship_design(&mut self, design_id: u32) -> Option<ShipDesign> {
+        for slot in self.ship_designs.iter_mut().chain(self.starbase_designs.iter_mut()) {
+            let matches = match slot {
+                Some(d) => d.id == design_id,
+                None => false
+            };
+
+            if matches {
+                return slot.take();
+            }
+        }
+
+        None
+    }
+
     pub fn get_best_starting_scanner(&self) -> TechnologyId {
         if self.available_tech_ids.contains(&TechnologyId::PossumScanner) {
             return TechnologyId::PossumScanner.clone();
@@ -124,6 +366,48 @@ impl Player {
         return TechnologyId::MoleskinShield.clone();
     }
 
+    pub fn can_use_technology(&self, tid: TechnologyId) -> bool {
+        self.learned_tech_ids.contains(&tid) || self.trader_items.contains(&tid)
+    }
+
+    /*
+        A design can only be built from the hull and parts the player has
+        learned or been given by the Mystery Trader.
+    */
+    pub fn can_use_design(&self, design: &ShipDesign) -> bool {
+        self.can_use_technology(design.base_hull)
+            && design.filled_slots().iter().all(|s| self.can_use_technology(s.tid))
+    }
+
+    /*
+        Raises a research field one level and learns whatever technology
+        the new level allows.  Returns false if the field is already at the
+        highest level.
+    */
+    pub fn advance_tech_level(&mut self, field: usize) -> bool {
+        if self.tech_level[field] >= MAX_TECH_LEVEL {
+            return false;
+        }
+
+        self.tech_level[field] += 1;
+        self.learned_tech_ids = calculate_initial_learned_technologies(&self.available_tech_ids, self.tech_level);
+        true
+    }
+
+    pub fn get_best_defense_quality(&self) -> Option<DefenseQuality> {
+        let qualities = [
+            DefenseQuality::Neutron,
+            DefenseQuality::Planet,
+            DefenseQuality::Laser,
+            DefenseQuality::Missile,
+            DefenseQuality::SDI
+        ];
+
+        qualities.iter()
+            .find(|q| self.learned_tech_ids.contains(&q.technology()))
+            .cloned()
+    }
+
     pub fn get_best_starting_laser(&self) -> TechnologyId {
         if self.available_tech_ids.contains(&TechnologyId::YakimoraLightPhaser) {
             return TechnologyId::YakimoraLightPhaser.clone();
@@ -38,6 +38,7 @@ pub enum ShipId {
     SmaugarianPeepingTom = 10,
     PotatoBug = 11,
     ShadowTransport = 12,
+    LittleHen = 13,
 }
 
 pub const ORIGINAL_GAME_SHIP_NAMES : &'static [&'static str] = &[
@@ -54,10 +55,11 @@ pub const ORIGINAL_GAME_SHIP_NAMES : &'static [&'static str] = &[
     "Smaugarian Peeping Tom",
     "Potato Bug",
     "Shadow Transport",
+    "Little Hen",
 ];
 
 //pub const NUMBER_OF_ORIGINAL_SHIP_DESIGNS : usize = ORIGINAL_GAME_SHIP_NAMES.len();
-pub const NUMBER_OF_ORIGINAL_SHIP_DESIGNS : usize = 13;
+pub const NUMBER_OF_ORIGINAL_SHIP_DESIGNS : usize = 14;
 
 
 pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: TechnologyId, best_shield: TechnologyId, best_scanner: TechnologyId, best_miner: TechnologyId) -> [ShipDesign; NUMBER_OF_ORIGINAL_SHIP_DESIGNS]  {
@@ -66,6 +68,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::SantaMaria as usize].to_string(),
         base_hull: TechnologyId::ColonyShip,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -85,6 +88,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::ShadowTransport as usize].to_string(),
         base_hull: TechnologyId::SmallFreighter,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -108,6 +112,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::PotatoBug as usize].to_string(),
         base_hull: TechnologyId::MidgetMiner,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -127,6 +132,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::SmaugarianPeepingTom as usize].to_string(),
         base_hull: TechnologyId::Scout,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -150,6 +156,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::Teamster as usize].to_string(),
         base_hull: TechnologyId::MediumFreighter,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -173,6 +180,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 1,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::CottonPicker as usize].to_string(),
         base_hull: TechnologyId::MiniMiner,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -200,6 +208,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::StalwartDefender as usize].to_string(),
         base_hull: TechnologyId::Destroyer,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -239,6 +248,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::Swashbuckler as usize].to_string(),
         base_hull: TechnologyId::Privateer,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -270,6 +280,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 0,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::LongRangeScout as usize].to_string(),
         base_hull: TechnologyId::Scout,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -293,6 +304,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 1,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::ArmedProbe as usize].to_string(),
         base_hull: TechnologyId::Scout,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -316,6 +328,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 1,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::Pinta as usize].to_string(),
         base_hull: TechnologyId::ColonyShip,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -335,6 +348,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 1,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::SporeCloud as usize].to_string(),
         base_hull: TechnologyId::MiniColonyShip,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: TechnologyId::SettlersDelight,
@@ -354,6 +368,7 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         icon_index: 2,
         name: ORIGINAL_GAME_SHIP_NAMES[ShipId::Mayflower as usize].to_string(),
         base_hull: TechnologyId::ColonyShip,
+        is_obsolete: false,
         slots: Some([
             Some(ShipSlot {
                 tid: best_engine,
@@ -368,6 +383,34 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         ])
     };
 
+    let little_hen = ShipDesign {
+        id: 0,
+        icon_index: 0,
+        name: ORIGINAL_GAME_SHIP_NAMES[ShipId::LittleHen as usize].to_string(),
+        base_hull: TechnologyId::MiniMineLayer,
+        is_obsolete: false,
+        slots: Some([
+            Some(ShipSlot {
+                tid: best_engine,
+                amount: 1
+            }),
+            Some(ShipSlot {
+                tid: TechnologyId::MineDispenser40,
+                amount: 2
+            }),
+            Some(ShipSlot {
+                tid: TechnologyId::MineDispenser40,
+                amount: 2
+            }),
+            Some(ShipSlot {
+                tid: best_scanner,
+                amount: 1
+            }),
+            None, None, None, None, None, None,
+            None, None, None, None, None, None
+        ])
+    };
+
     return [
         santa_maria, 
         armed_probe, 
@@ -381,7 +424,8 @@ pub fn construct_initial_ship_designs(best_engine: TechnologyId, best_laser: Tec
         cotton_picker,
         smaugarian_peeping_tom,
         potato_bug,
-        shadow_transport
+        shadow_transport,
+        little_hen
     ];
 }
 
@@ -389,3 +433,86 @@ pub const ORIGINAL_GAME_STARBASE_NAMES : &'static [&'static str] = &[
     "Space Station",
     "Portal to Nowhere"
 ];
+
+#[derive(Serialize, Deserialize, Copy, Clone)]
+pub enum StarbaseId {
+    SpaceStation = 0,
+    PortalToNowhere = 1
+}
+
+pub const NUMBER_OF_ORIGINAL_STARBASE_DESIGNS : usize = 2;
+
+pub fn construct_initial_starbase_designs(best_laser: TechnologyId, best_shield: TechnologyId) -> [ShipDesign; NUMBER_OF_ORIGINAL_STARBASE_DESIGNS] {
+    let space_station = ShipDesign {
+        id: 0,
+        icon_index: 0,
+        name: ORIGINAL_GAME_STARBASE_NAMES[StarbaseId::SpaceStation as usize].to_string(),
+        base_hull: TechnologyId::SpaceStation,
+        is_obsolete: false,
+        slots: Some([
+            Some(ShipSlot {
+                tid: best_laser,
+                amount: 8
+            }),
+            Some(ShipSlot {
+                tid: best_laser,
+                amount: 8
+            }),
+            None, None,
+            Some(ShipSlot {
+                tid: best_shield,
+                amount: 8
+            }),
+            None, None, None, None, None, None,
+            None, None, None, None, None
+        ])
+    };
+
+    let portal_to_nowhere = ShipDesign {
+        id: 0,
+        icon_index: 1,
+        name: ORIGINAL_GAME_STARBASE_NAMES[StarbaseId::PortalToNowhere as usize].to_string(),
+        base_hull: TechnologyId::SpaceStation,
+        is_obsolete: false,
+        slots: Some([
+            Some(ShipSlot {
+                tid: best_laser,
+                amount: 8
+            }),
+            None, None, None,
+            Some(ShipSlot {
+                tid: best_shield,
+                amount: 8
+            }),
+            None, None, None, None, None,
+            Some(ShipSlot {
+                tid: TechnologyId::Stargate100_250,
+                amount: 1
+            }),
+            None, None, None, None, None
+        ])
+    };
+
+    [
+        space_station,
+        portal_to_nowhere
+    ]
+}
+
+pub const STARTER_COLONY_NAME : &str = "Starter Colony";
+
+// Alternate Reality races live in orbit, so colonizing with an Orbital
+// Construction Module leaves behind an empty Orbital Fort.
+pub fn construct_starter_colony_design() -> ShipDesign {
+    ShipDesign {
+        id: 0,
+        icon_index: 0,
+        name: STARTER_COLONY_NAME.to_string(),
+        base_hull: TechnologyId::OrbitalFort,
+        is_obsolete: false,
+        slots: Some([
+            None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None, None, None
+        ])
+    }
+}
@@ -235,8 +235,8 @@ pub const ORIGINAL_GAME_MESSAGES: &'static [&'static str] = &[
     "Your mass accelerator at {0} has successfully captured a packet containing {1}kT of minerals.",
     "Your mass accelerator at {0} was partially successfully in capturing a {1}kT mineral packet. Unable to completely slow the packet, {2} of your colonists were killed in the collision.",
     "Your mass accelerator at {0} was partially successfully in capturing a {1}kT mineral packet. Unfortunately, {2} of your colonists and {3} of your defenses were destroyed in the collision.",
-    "{0} was bombarded with a {1}kT mineral packet. {1} of your colonists were killed by the collision.",
-    "{0} was bombarded with a {1}kT mineral packet. {1} of your colonists and {2} of your defenses were destroyed by the collision.",
+    "{0} was bombarded with a {1}kT mineral packet. {2} of your colonists were killed by the collision.",
+    "{0} was bombarded with a {1}kT mineral packet. {2} of your colonists and {3} of your defenses were destroyed by the collision.",
     "{0} was annihilated by a mineral packet.  All of your colonists were killed.",
     "{0} didn't get all the {1} it attempted to transfer from the mineral packet as another player stole it first. Only {2}kT of {3} was actually acquired.",
     "{0} didn't get any of the {1} it attempted to transfer from the mineral packet as another player stole it first.",
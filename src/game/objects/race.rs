@@ -38,9 +38,13 @@ use game::objects::tech::SUPER_STEALTH_TECHNOLOGY;
 use game::objects::tech::HYPER_EXPANSION_TECHNOLOGY;
 use game::objects::tech::ALTERNATE_REALITY_TECHNOLOGY;
 use game::objects::universe::UniverseSize;
+use game::objects::planet::HabitatLevel;
 
 pub const BASE_STARTING_PLANET_POPULATION : u32 = 25000;
 
+// The race wizard only lets this many leftover points be spent at game start
+pub const MAX_SPENDABLE_LEFTOVER_POINTS : i16 = 50;
+
 #[derive(Serialize, Deserialize, PartialEq)]
 pub enum PrimaryRacialTrait {
     ClaimAdjuster,
@@ -207,6 +211,30 @@ impl Race {
         return f32::round(((self.gravity_min as f32) + (self.gravity_max as f32)) / 2.0) as u8;
     }
 
+    /*
+        How well the race can live on a planet, from 0 for a planet outside
+        any of its ranges to 1 for one at its ideal on every axis.  Each axis
+        falls off linearly from the middle of the race's range to its edges.
+    */
+    pub fn planet_habitability(&self, habitat: &HabitatLevel) -> f64 {
+        let axis = |immune: bool, min: u8, max: u8, value: u8| -> f64 {
+            if immune {
+                return 1.0;
+            }
+            if value < min || value > max {
+                return 0.0;
+            }
+
+            let center = (min as f64 + max as f64) / 2.0;
+            let half_width = ((max as f64 - min as f64) / 2.0).max(1.0);
+            1.0 - (value as f64 - center).abs() / half_width
+        };
+
+        axis(self.gravity_immune, self.gravity_min, self.gravity_max, habitat.gravity)
+            * axis(self.temperature_immune, self.temperature_min, self.temperature_max, habitat.temperature)
+            * axis(self.radiation_immune, self.radiation_min, self.radiation_max, habitat.radiation)
+    }
+
     pub fn calculate_advantage_points(&self) -> i16 {
         let mut calculated: i16 = 0;
         calculated += self.primary_racial_trait.advantage_points();
@@ -226,6 +254,10 @@ impl Race {
         return calculated;
     }
 
+    pub fn spendable_leftover_points(&self) -> u16 {
+        self.advantage_points.clamp(0, MAX_SPENDABLE_LEFTOVER_POINTS) as u16
+    }
+
     pub fn calculate_starting_population(&self, universe_size: UniverseSize) -> u32 {
         let mut population = BASE_STARTING_PLANET_POPULATION;
 
@@ -0,0 +1,59 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::fleet::ShipDesign;
+
+/*
+    A starbase is an orbital object tied to a single planet.  Unlike a fleet
+    it never moves, and the planet it orbits can only build ships when the
+    starbase design has a dock large enough for the ship.
+*/
+#[derive(Serialize, Deserialize)]
+pub struct Starbase {
+    pub id: u32,
+    pub owner_id: u8,
+    pub planet_id: u32,
+    pub design_id: u32,
+    pub damage: u32
+}
+
+impl Starbase {
+    pub fn construct(id: u32, owner_id: u8, planet_id: u32, design: &ShipDesign) -> Starbase {
+        Starbase {
+            id,
+            owner_id,
+            planet_id,
+            design_id: design.id,
+            damage: 0
+        }
+    }
+
+    pub fn remaining_armor(&self, design: &ShipDesign) -> u32 {
+        design.calculate_armor().saturating_sub(self.damage)
+    }
+
+    pub fn can_build(&self, design: &ShipDesign, ship: &ShipDesign) -> bool {
+        match design.dock_capacity() {
+            Some(capacity) => ship.calculate_mass() <= capacity,
+            None => false
+        }
+    }
+}
@@ -19,8 +19,8 @@
  *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
  *  DEALINGS IN THE SOFTWARE.
  */
-const STARGATE_INFINITE_VALUE : u16 = 0;
-const DOCK_CAPACITY_INFINITE_VALUE : u16 = 5000;
+pub const STARGATE_INFINITE_VALUE : u16 = 0;
+pub const DOCK_CAPACITY_INFINITE_VALUE : u16 = 5000;
 
 const TECHNOLOGY_BASE_COSTS : &'static [u32] = &[
     0,
@@ -90,13 +90,13 @@ pub fn calculate_cost_to_next_tech_level(current_level : u8, total_levels : u16,
 
 #[derive(Serialize, Deserialize)]
 pub struct TechnologyCost {
-    pub ironium: u16,
-    pub boranium: u16,
-    pub germanium: u16,
-    pub resources: u16,
+    pub ironium: u32,
+    pub boranium: u32,
+    pub germanium: u32,
+    pub resources: u32,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
 pub enum MineType {
     Normal,
     Heavy,
@@ -126,12 +126,64 @@ pub enum TechnologySlotType {
     MineLayer
 }
 
+impl TechnologySlotType {
+    /*
+        Which component categories a hull slot will hold, following the slot
+        names in the Stars! ship designer.  General purpose slots take
+        anything but engines and orbital devices.
+    */
+    pub fn accepts(&self, category: TechnologyCategory) -> bool {
+        use self::TechnologyCategory::*;
+
+        match *self {
+            TechnologySlotType::Weapon => category == Weapon,
+            TechnologySlotType::Electrical => category == Electrical,
+            TechnologySlotType::Shield => category == Shield,
+            TechnologySlotType::Armor => category == Armor,
+            TechnologySlotType::Protection => category == Armor || category == Shield,
+            TechnologySlotType::OrbitalElect => category == Orbital || category == Electrical,
+            TechnologySlotType::Engine => category == Engine,
+            TechnologySlotType::ScannerElectMech => category == Scanner || category == Electrical || category == Mechanical,
+            TechnologySlotType::GeneralPurpose => matches!(category,
+                Armor | Shield | Weapon | Bomb | Scanner | Electrical | Mechanical | MiningRobot | MineLayer),
+            TechnologySlotType::Mechanical => category == Mechanical,
+            TechnologySlotType::ShieldElectMech => category == Shield || category == Electrical || category == Mechanical,
+            TechnologySlotType::WeaponShield => category == Weapon || category == Shield,
+            TechnologySlotType::MineElectMech => category == MineLayer || category == Electrical || category == Mechanical,
+            TechnologySlotType::Scanner => category == Scanner,
+            TechnologySlotType::Bomb => category == Bomb,
+            TechnologySlotType::MiningRobot => category == MiningRobot,
+            TechnologySlotType::ArmorScannerElectMech => category == Armor || category == Scanner || category == Electrical || category == Mechanical,
+            TechnologySlotType::ElectMech => category == Electrical || category == Mechanical,
+            TechnologySlotType::MineLayer => category == MineLayer
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct TechnologySlot {
     pub slot_type: TechnologySlotType,
     pub amount: u8
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq)]
+pub enum TechnologyCategory {
+    Planetary,
+    Terraforming,
+    Armor,
+    Shield,
+    MineLayer,
+    Scanner,
+    Mechanical,
+    Electrical,
+    MiningRobot,
+    Engine,
+    Bomb,
+    Orbital,
+    Hull,
+    Weapon
+}
+
 
 #[derive(Serialize, Deserialize)]
 pub struct TechnologyRequirement {
@@ -382,6 +434,42 @@ pub enum TechnologyId {
     MiniMorph = 238
 }
 
+impl TechnologyId {
+    /*
+        The ship designer category of a technology.  Technology ids are laid
+        out in category order, with the Mystery Trader parts at the end.
+    */
+    pub fn category(self) -> TechnologyCategory {
+        match self as u16 {
+            0..=13 => TechnologyCategory::Planetary,
+            14..=24 => TechnologyCategory::Armor,
+            25..=33 => TechnologyCategory::Shield,
+            34..=43 => TechnologyCategory::MineLayer,
+            44..=59 => TechnologyCategory::Scanner,
+            60..=68 => TechnologyCategory::Mechanical,
+            69..=84 => TechnologyCategory::Electrical,
+            85..=104 => TechnologyCategory::Terraforming,
+            105..=111 => TechnologyCategory::MiningRobot,
+            112..=126 => TechnologyCategory::Engine,
+            127..=140 => TechnologyCategory::Bomb,
+            141..=156 => TechnologyCategory::Orbital,
+            157..=192 => TechnologyCategory::Hull,
+            193..=226 => TechnologyCategory::Weapon,
+            _ => match self {
+                TechnologyId::Hushaboom => TechnologyCategory::Bomb,
+                TechnologyId::EnigmaPulsar => TechnologyCategory::Engine,
+                TechnologyId::MegaPolyShell | TechnologyId::LangstonShell => TechnologyCategory::Armor,
+                TechnologyId::MultiFunctionPod => TechnologyCategory::Electrical,
+                TechnologyId::AntiMatterTorpedo | TechnologyId::MultiContainedMunition => TechnologyCategory::Weapon,
+                TechnologyId::JumpGate | TechnologyId::MultiCargoPod => TechnologyCategory::Mechanical,
+                TechnologyId::GenesisDevice => TechnologyCategory::Orbital,
+                TechnologyId::AlienMiner => TechnologyCategory::MiningRobot,
+                _ => TechnologyCategory::Hull
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Technology {
     pub requirement: TechnologyRequirement,
@@ -8968,6 +9056,22 @@ pub const HYPER_EXPANSION_TECHNOLOGY: &'static [TechnologyId] = &[
     TechnologyId::AnnihilatorBomb
 ];
 
+// Only obtainable from the Mystery Trader
+pub const MYSTERY_TRADER_TECHNOLOGY: &[TechnologyId] = &[
+    TechnologyId::Hushaboom,
+    TechnologyId::EnigmaPulsar,
+    TechnologyId::MegaPolyShell,
+    TechnologyId::LangstonShell,
+    TechnologyId::MultiFunctionPod,
+    TechnologyId::AntiMatterTorpedo,
+    TechnologyId::JumpGate,
+    TechnologyId::GenesisDevice,
+    TechnologyId::MultiContainedMunition,
+    TechnologyId::AlienMiner,
+    TechnologyId::MultiCargoPod,
+    TechnologyId::MiniMorph
+];
+
 pub const ALTERNATE_REALITY_TECHNOLOGY: &'static [TechnologyId] = &[
     TechnologyId::DeathStar,
     TechnologyId::Stargate100_250,
@@ -8983,6 +9087,10 @@ pub const ALTERNATE_REALITY_TECHNOLOGY: &'static [TechnologyId] = &[
 ];
 
 
+pub const MAX_TECH_LEVEL : u8 = 26;
+
+pub const RESEARCH_FIELD_NAMES : [&str; 6] = ["Energy", "Weapons", "Propulsion", "Construction", "Electronics", "Biotechnology"];
+
 #[derive(Serialize, Deserialize)]
 pub enum ResearchField {
     Energy,
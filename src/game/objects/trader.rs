@@ -0,0 +1,63 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::universe::Heading;
+use ::game::objects::tech::TechnologyId;
+
+// Years between the Mystery Trader's passes through known space
+pub const MYSTERY_TRADER_INTERVAL : u32 = 25;
+
+// Minerals (in kT) a fleet must bring before the trader will deal with it
+pub const MYSTERY_TRADER_MIN_MINERALS : u32 = 5000;
+
+// Items the trader carries on each pass
+pub const MYSTERY_TRADER_STOCK : usize = 3;
+
+pub const MYSTERY_TRADER_MAX_TECH_ADVANCES : u32 = 3;
+
+pub const MYSTERY_TRADER_MIN_WARP : u8 = 5;
+pub const MYSTERY_TRADER_MAX_WARP : u8 = 9;
+
+#[derive(Serialize, Deserialize)]
+pub struct MysteryTrader {
+    pub id: u32,
+    pub location: SpaceCoordinate,
+    pub heading: Heading,
+    pub items: Vec<TechnologyId>,
+
+    // Players who have already traded with this trader on its current pass
+    pub customers: Vec<u8>
+}
+
+pub enum TraderItemKind {
+    Part,
+    Hull,
+    PlanetaryDevice
+}
+
+pub fn trader_item_kind(tid: TechnologyId) -> TraderItemKind {
+    match tid {
+        TechnologyId::MiniMorph => TraderItemKind::Hull,
+        TechnologyId::GenesisDevice => TraderItemKind::PlanetaryDevice,
+        _ => TraderItemKind::Part
+    }
+}
@@ -22,38 +22,97 @@
 use ::game::objects::game::PlayerStartingDistance;
 use ::game::objects::planet::Planet;
 use ::game::objects::fleet::ShipDesign;
-use ::game::objects::fleet::ShipOrder;
 use ::game::objects::fleet::ShipOrderType;
+use ::game::objects::fleet::Waypoint;
+use ::game::objects::fleet::WaypointTarget;
 use ::game::objects::fleet::Fleet;
 use ::game::objects::fleet::FleetMember;
+use ::game::objects::fleet::Cargo;
+use ::game::objects::fleet::CargoType;
+use ::game::objects::fleet::CargoTransfer;
+use ::game::objects::fleet::TransferAmount;
+use ::game::objects::message::MessageId;
+use ::game::objects::tech::MineType;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::fleet::MAX_DESIGN_IDS_PER_PLAYER;
 use ::game::objects::fleet::MAX_SHIP_DESIGNS;
+use ::game::objects::fleet::MAX_STARBASE_DESIGNS;
+use ::game::objects::trader::MysteryTrader;
+use ::game::objects::planet::BuildItem;
+use ::game::objects::starbase::Starbase;
+use ::game::objects::player::PlayerRelation;
 use rand;
 use rand::Rng;
 use std::collections::HashMap;
 use ::game::objects::predefined::fleets::construct_initial_ship_designs;
 use ::game::objects::predefined::fleets::ShipId;
+use ::game::objects::predefined::fleets::construct_initial_starbase_designs;
+use ::game::objects::predefined::fleets::StarbaseId;
 use ::game::objects::race::PrimaryRacialTrait;
 use ::game::objects::race::LesserRacialTrait;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct SpaceCoordinate {
     pub x: u16,
     pub y: u16
 }
 
+impl SpaceCoordinate {
+    pub fn distance_to(&self, other: &SpaceCoordinate) -> f64 {
+        let dx = other.x as f64 - self.x as f64;
+        let dy = other.y as f64 - self.y as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    pub fn is_same_location(&self, other: &SpaceCoordinate) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+
+    /*
+        Point reached after travelling the given distance in a straight line
+        toward the target, rounded to the nearest light year.
+    */
+    pub fn step_toward(&self, target: &SpaceCoordinate, distance: f64) -> SpaceCoordinate {
+        let total = self.distance_to(target);
+        if distance >= total || total == 0.0 {
+            return target.clone();
+        }
+
+        let fraction = distance / total;
+        SpaceCoordinate {
+            x: (self.x as f64 + (target.x as f64 - self.x as f64) * fraction).round() as u16,
+            y: (self.y as f64 + (target.y as f64 - self.y as f64) * fraction).round() as u16
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Heading {
     pub warp: u8,
     pub next_location: SpaceCoordinate
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct MineralContents {
     pub boranium: u16,
     pub germanium: u16,
     pub ironium: u16
 }
 
+impl MineralContents {
+    // Surface minerals are capped at what a u16 can hold
+    pub fn add_saturating(&mut self, ironium: u32, boranium: u32, germanium: u32) {
+        let cap = |current: u16, amount: u32| (current as u32 + amount).min(u16::MAX as u32) as u16;
+        self.ironium = cap(self.ironium, ironium);
+        self.boranium = cap(self.boranium, boranium);
+        self.germanium = cap(self.germanium, germanium);
+    }
+
+    pub fn total(&self) -> u32 {
+        self.ironium as u32 + self.boranium as u32 + self.germanium as u32
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum WormholeStability {
     Stable,
@@ -101,28 +160,91 @@ pub struct Wormhole {
 
 #[derive(Serialize, Deserialize)]
 pub struct Salvage {
-    id: u32,
-    location: SpaceCoordinate,
+    pub id: u32,
+    pub location: SpaceCoordinate,
 
-    minerals: MineralContents
+    pub minerals: MineralContents
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Minefield {
-    id: u32,
-    location: SpaceCoordinate,
+    pub id: u32,
+    pub location: SpaceCoordinate,
+
+    pub owner_id: u8,
+    pub mine_type: MineType,
+    pub mines: u32,
+
+    // Players who currently know where this field is
+    pub detected_by: Vec<u8>
+}
+
+impl Minefield {
+    pub fn radius(&self) -> f64 {
+        (self.mines as f64).sqrt()
+    }
+
+    pub fn contains(&self, location: &SpaceCoordinate) -> bool {
+        self.location.distance_to(location) <= self.radius()
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.mine_type {
+            MineType::Normal => "standard",
+            MineType::Heavy => "heavy",
+            MineType::Speed => "speed bump"
+        }
+    }
+
+    // Fleets moving at or below this warp pass through safely
+    pub fn safe_warp(&self) -> u8 {
+        match self.mine_type {
+            MineType::Normal => 4,
+            MineType::Heavy => 6,
+            MineType::Speed => 5
+        }
+    }
+
+    /*
+        Chance, in percent, of hitting a mine for each light year travelled
+        at the given warp.  The chance grows with every warp step above the
+        field's safe warp.
+    */
+    pub fn hit_chance_per_light_year(&self, warp: u8) -> f64 {
+        let per_warp = match self.mine_type {
+            MineType::Normal => 0.3,
+            MineType::Heavy => 1.0,
+            MineType::Speed => 3.5
+        };
 
-    owner_id: u8
+        per_warp * warp.saturating_sub(self.safe_warp()) as f64
+    }
+
+    /*
+        Damage dealt to each ship in a fleet that hits a mine, and the
+        least damage the fleet as a whole takes.  Ram scoop engines suffer
+        more.  Speed bumps only stop fleets.
+    */
+    pub fn damage(&self, ram_scoop: bool) -> (u32, u32) {
+        match (self.mine_type, ram_scoop) {
+            (MineType::Normal, false) => (100, 500),
+            (MineType::Normal, true) => (125, 600),
+            (MineType::Heavy, false) => (500, 2000),
+            (MineType::Heavy, true) => (600, 2500),
+            (MineType::Speed, _) => (0, 0)
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct MineralPacket {
-    id: u32,
-    location: SpaceCoordinate,
+    pub id: u32,
+    pub location: SpaceCoordinate,
 
-    heading: Heading,
-    owner_id: u8,
-    minerals: MineralContents
+    pub heading: Heading,
+    pub owner_id: u8,
+    pub destination_id: u32,
+    pub minerals: MineralContents
 }
 
 pub fn get_coordinate_square(dimension: u16) -> Vec<SpaceCoordinate> {
@@ -257,12 +379,17 @@ pub struct Universe {
     pub salvage: Vec<Salvage>,
     pub minefields: Vec<Minefield>,
     pub mineral_packets: Vec<MineralPacket>,
+    #[serde(default)]
+    pub mystery_traders: Vec<MysteryTrader>,
     pub planets: Vec<::game::objects::planet::Planet>,
     pub fleets: HashMap<u32, ::game::objects::fleet::Fleet>,
+    #[serde(default)]
+    pub starbases: HashMap<u32, Starbase>,
     pub players: Vec<::game::objects::player::Player>
 }
 
 pub const MAX_FLEETS : u32 = 30000;
+pub const MAX_STARBASES : u32 = 30000;
 
 impl Universe {
     pub fn construct_random(size: &UniverseSize, density: &UniverseDensity, galaxy_clumping: &bool, starting_distance: &PlayerStartingDistance) -> Universe {
@@ -272,8 +399,10 @@ impl Universe {
             salvage: Vec::new(),
             minefields: Vec::new(),
             mineral_packets: Vec::new(),
+            mystery_traders: Vec::new(),
             planets: generate_random_planet_configuration(size, density, galaxy_clumping),
             fleets: HashMap::new(),
+            starbases: HashMap::new(),
             players: Vec::new()
         };
 
@@ -292,55 +421,997 @@ impl Universe {
         return id;
     }
 
+    pub fn get_new_starbase_id(&self) -> u32 {
+        let mut rng = rand::thread_rng();
+        let mut id : u32 = 0;
+
+        while self.starbases.contains_key(&id) {
+            let multiplier: f64 = rng.gen();
+            id = ((MAX_STARBASES as f64) * multiplier).round() as u32;
+        }
+
+        id
+    }
+
+    pub fn add_salvage(&mut self, location: SpaceCoordinate, minerals: MineralContents) -> u32 {
+        let id = self.salvage.iter().map(|s| s.id + 1).max().unwrap_or(0);
+        self.salvage.push(Salvage {
+            id,
+            location,
+            minerals
+        });
+
+        id
+    }
+
+    /*
+        Name used for a location in messages: the planet there, or the
+        coordinates in deep space.
+    */
+    pub fn describe_location(&self, location: &SpaceCoordinate) -> String {
+        match self.planets.iter().find(|p| p.location.is_same_location(location)) {
+            Some(p) => p.name.clone(),
+            None => format!("({}, {})", location.x, location.y)
+        }
+    }
+
+    pub fn get_planet(&self, planet_id: u32) -> Option<&Planet> {
+        self.planets.iter().find(|p| p.id == planet_id)
+    }
+
+    pub fn get_planet_mut(&mut self, planet_id: u32) -> Option<&mut Planet> {
+        self.planets.iter_mut().find(|p| p.id == planet_id)
+    }
+
+    /*
+        Older saves numbered designs as player * 16 + slot, which the current
+        scheme would read as belonging to player 0.  Renumbers every design
+        and points the fleets and build queues flying or building it at the
+        new id.
+    */
+    pub fn renumber_legacy_designs(&mut self) {
+        let mut new_ids : HashMap<u32, u32> = HashMap::new();
+        for p in self.players.iter_mut() {
+            new_ids.extend(p.renumber_designs());
+        }
+
+        for f in self.fleets.values_mut() {
+            for m in f.members.iter_mut() {
+                if let Some(id) = new_ids.get(&m.design_id) {
+                    m.design_id = *id;
+                }
+            }
+        }
+
+        for p in self.planets.iter_mut() {
+            for item in p.production_queue.iter_mut() {
+                if let Some(id) = new_ids.get(&item.design_id) {
+                    item.design_id = *id;
+                }
+            }
+        }
+    }
+
+    /*
+        Orders saved before fleets had waypoints held no destinations, so
+        every one of them applies where the fleet already is.
+    */
+    pub fn place_legacy_waypoints(&mut self) {
+        for f in self.fleets.values_mut() {
+            for w in f.orders.iter_mut() {
+                w.location = f.location.clone();
+            }
+
+            if f.orders.is_empty() {
+                f.orders.push(Waypoint::construct(WaypointTarget::Space, f.location.clone(), 0, ShipOrderType::NoTask));
+            }
+        }
+    }
+
+    // Fleets saved before they had cargo holds start with empty holds
+    pub fn size_legacy_cargo_holds(&mut self) {
+        let capacities : Vec<(u32, u32)> = self.fleets.values()
+            .map(|f| (f.id, Fleet::calculate_total_cargo_capacity(self, &f.members)))
+            .collect();
+
+        for (fid, capacity) in capacities {
+            if let Some(f) = self.fleets.get_mut(&fid) {
+                f.total_cargo_capacity = capacity;
+            }
+        }
+    }
+
+    /*
+        Remembers each planet's habitat as it was when the universe was
+        created, so that retro bombs know what terraforming to undo.
+    */
+    pub fn record_original_habitats(&mut self) {
+        for p in self.planets.iter_mut().filter(|p| p.original_habitat.is_none()) {
+            p.original_habitat = Some(p.habitat.clone());
+        }
+    }
+
+    // Fleets saved before they had names are named as new fleets are
+    pub fn name_legacy_fleets(&mut self) {
+        let mut names : Vec<(u32, String)> = Vec::new();
+        for f in self.fleets.values().filter(|f| f.name.is_empty()) {
+            if let Some(d) = f.members.first().and_then(|m| self.lookup_ship_design(m.design_id)) {
+                names.push((f.id, format!("{} #{}", d.name, f.id)));
+            }
+        }
+
+        for (fid, name) in names {
+            if let Some(f) = self.fleets.get_mut(&fid) {
+                f.name = name;
+            }
+        }
+    }
+
     pub fn lookup_ship_design(&self, id : u32) -> Option<ShipDesign> {
-        let pid = id / (MAX_SHIP_DESIGNS as u32);
-        let offset = id % (MAX_SHIP_DESIGNS as u32);
-        info!("id:{}, pid:{}, offset:{}", id, pid, offset);
+        let pid = id / MAX_DESIGN_IDS_PER_PLAYER;
 
-        let p = &self.players[pid as usize];
-        let design = &p.ship_designs[offset as usize];
+        match self.players.get(pid as usize) {
+            Some(p) => p.lookup_design(id).cloned(),
+            None => {
+                error!("No player owns ship design id:{}", id);
+                None
+            }
+        }
+    }
+
+    /*
+        Adds a new design to a player's ship or starbase designs.  The
+        design is refused if it uses a hull or part the player has neither
+        learned nor bought from the Mystery Trader.  Returns the design's
+        slot.
+    */
+    pub fn add_ship_design(&mut self, player_id: u8, design: ShipDesign) -> Option<u8> {
+        let player = &mut self.players[player_id as usize];
 
-        match design {
-            Some(d) => { Some(d.clone()) }
-            None => { None }
+        if !player.can_use_design(&design) {
+            error!("Player {} tried to add design {} with technology they do not have", player_id, design.name);
+            return None;
+        }
+
+        if !design.fits_hull() {
+            error!("Player {} tried to add design {} that does not fit its hull", player_id, design.name);
+            return None;
+        }
+
+        if design.is_starbase() {
+            let index = player.add_starbase_design(design);
+            if index < MAX_STARBASE_DESIGNS { Some(index) } else { None }
+        }
+        else {
+            let index = player.add_ship_design(design);
+            if index < MAX_SHIP_DESIGNS { Some(index) } else { None }
         }
     }
 
+    /*
+        Deletes a design from its owner's design slots.
+
+        In the original game, deleting a design also scrapped every ship built
+        from it.  With scrap_fleets unset the ships are instead left flying and
+        the design is retired, so that lookups for those fleets keep working.
+    */
+    pub fn delete_ship_design(&mut self, player_id: u8, design_id: u32, scrap_fleets: bool) -> bool {
+        let design = match self.players.get_mut(player_id as usize) {
+            Some(p) => p.remove_ship_design(design_id),
+            None => None
+        };
+
+        let design = match design {
+            Some(d) => d,
+            None => {
+                error!("Player {} tried to delete unknown design {}", player_id, design_id);
+                return false;
+            }
+        };
+
+        if !scrap_fleets {
+            self.players[player_id as usize].retired_designs.push(design);
+            return true;
+        }
+
+        let mut emptied_fleets = Vec::new();
+        let mut changed_fleets = Vec::new();
+        for f in self.fleets.values_mut() {
+            if f.owner_id != Some(player_id) {
+                continue;
+            }
+
+            let before = f.members.len();
+            f.members.retain(|m| m.design_id != design_id);
+
+            if f.members.is_empty() {
+                emptied_fleets.push(f.id);
+            }
+            else if f.members.len() != before {
+                changed_fleets.push(f.id);
+            }
+        }
+
+        for fid in changed_fleets {
+            self.recalculate_fleet_capacities(fid);
+        }
+
+        for fid in emptied_fleets {
+            self.remove_fleet(fid);
+        }
+
+        true
+    }
+
+    pub fn copy_ship_design(&mut self, player_id: u8, design_id: u32) -> Option<u8> {
+        let index = match self.players.get_mut(player_id as usize) {
+            Some(p) => p.copy_ship_design(design_id),
+            None => None
+        };
+
+        if index.is_none() {
+            error!("Player {} cannot copy design {}", player_id, design_id);
+        }
+        index
+    }
+
+    pub fn rename_ship_design(&mut self, player_id: u8, design_id: u32, name: &str) -> bool {
+        let renamed = match self.players.get_mut(player_id as usize) {
+            Some(p) => p.rename_ship_design(design_id, name),
+            None => false
+        };
+
+        if !renamed {
+            error!("Player {} cannot rename design {}", player_id, design_id);
+        }
+        renamed
+    }
+
+    pub fn set_ship_design_obsolete(&mut self, player_id: u8, design_id: u32, is_obsolete: bool) -> bool {
+        let changed = match self.players.get_mut(player_id as usize) {
+            Some(p) => p.set_ship_design_obsolete(design_id, is_obsolete),
+            None => false
+        };
+
+        if !changed {
+            error!("Player {} cannot change obsolete flag of design {}", player_id, design_id);
+        }
+        changed
+    }
+
+    /*
+        Recomputes a fleet's fuel and cargo capacities after its ships
+        change.  Fuel above the new capacity is lost.
+    */
+    pub fn recalculate_fleet_capacities(&mut self, fleet_id: u32) {
+        let (fuel_capacity, cargo_capacity) = match self.fleets.get(&fleet_id) {
+            Some(f) => (Fleet::calculate_total_fuel_capacity(self, &f.members), Fleet::calculate_total_cargo_capacity(self, &f.members)),
+            None => return
+        };
+
+        let f = self.fleets.get_mut(&fleet_id).unwrap();
+        f.total_fuel_capacity = fuel_capacity;
+        f.current_fuel = f.current_fuel.min(fuel_capacity);
+        f.total_cargo_capacity = cargo_capacity;
+    }
+
+    fn owned_fleets_at_same_location(&self, player_id: u8, fleet_id: u32, other_id: u32) -> bool {
+        match (self.fleets.get(&fleet_id), self.fleets.get(&other_id)) {
+            (Some(a), Some(b)) => {
+                fleet_id != other_id && a.owner_id == Some(player_id) && b.owner_id == Some(player_id)
+                    && a.location.is_same_location(&b.location)
+            },
+            _ => false
+        }
+    }
+
+    /*
+        Moves every ship, along with its fuel and cargo, from one fleet into
+        another fleet of the same player at the same location.  The source
+        fleet is removed.
+    */
+    pub fn merge_fleets(&mut self, player_id: u8, fleet_id: u32, target_fleet_id: u32) -> bool {
+        if !self.owned_fleets_at_same_location(player_id, fleet_id, target_fleet_id) {
+            error!("Player {} cannot merge fleet {} into fleet {}", player_id, fleet_id, target_fleet_id);
+            return false;
+        }
+
+        let source = self.remove_fleet(fleet_id).unwrap();
+        let target_name = {
+            let target = self.fleets.get_mut(&target_fleet_id).unwrap();
+            for m in source.members.iter() {
+                target.add_members(m.design_id, m.quantity, m.damage);
+            }
+
+            target.current_fuel += source.current_fuel;
+            target.cargo.ironium += source.cargo.ironium;
+            target.cargo.boranium += source.cargo.boranium;
+            target.cargo.germanium += source.cargo.germanium;
+            target.cargo.colonists += source.cargo.colonists;
+            target.name.clone()
+        };
+
+        self.recalculate_fleet_capacities(target_fleet_id);
+        self.players[player_id as usize].add_message(MessageId::FleetMerged, vec![source.name, target_name]);
+        true
+    }
+
+    /*
+        Splits ships of one design off into a new fleet.  Fuel and each kind
+        of cargo are divided in proportion to the fuel and cargo capacity
+        each fleet ends up with.  The new fleet keeps the original orders
+        and battle plan.
+    */
+    pub fn split_fleet(&mut self, player_id: u8, fleet_id: u32, design_id: u32, quantity: u16) -> Option<u32> {
+        let (location, old_fuel_capacity, old_cargo_capacity, fuel, cargo, orders, repeat_orders, battle_plan) = match self.fleets.get(&fleet_id) {
+            Some(f) if f.owner_id == Some(player_id) => {
+                let available = f.members.iter().find(|m| m.design_id == design_id).map_or(0, |m| m.quantity);
+                if quantity == 0 || available < quantity || f.ship_count() <= quantity as u32 {
+                    error!("Player {} cannot split {} ships of design {} from fleet {}", player_id, quantity, design_id, fleet_id);
+                    return None;
+                }
+
+                (f.location.clone(), f.total_fuel_capacity, f.total_cargo_capacity, f.current_fuel, f.cargo.clone(), f.orders.clone(), f.repeat_orders, f.battle_plan)
+            },
+            _ => {
+                error!("Player {} tried to split fleet {} which they do not own", player_id, fleet_id);
+                return None;
+            }
+        };
+
+        let design = self.lookup_ship_design(design_id)?;
+        let new_id = self.add_fleet(&design, Some(player_id), location.clone(), quantity);
+
+        let split_damage = {
+            let f = self.fleets.get_mut(&fleet_id).unwrap();
+            let mut split_damage = 0;
+            for m in f.members.iter_mut() {
+                if m.design_id == design_id {
+                    split_damage = m.damage * quantity as u32 / m.quantity as u32;
+                    m.quantity -= quantity;
+                    m.damage -= split_damage;
+                }
+            }
+            f.members.retain(|m| m.quantity > 0);
+            split_damage
+        };
+
+        let (new_fuel_capacity, new_cargo_capacity) = {
+            let f = &self.fleets[&new_id];
+            (f.total_fuel_capacity, f.total_cargo_capacity)
+        };
+
+        let share = |amount: u32, part: u32, whole: u32| -> u32 {
+            if whole == 0 { 0 } else { (amount as u64 * part as u64 / whole as u64) as u32 }
+        };
+
+        let split_fuel = share(fuel, new_fuel_capacity, old_fuel_capacity);
+        let split_ironium = share(cargo.ironium, new_cargo_capacity, old_cargo_capacity);
+        let split_boranium = share(cargo.boranium, new_cargo_capacity, old_cargo_capacity);
+        let split_germanium = share(cargo.germanium, new_cargo_capacity, old_cargo_capacity);
+        let split_colonists = share(cargo.colonists, new_cargo_capacity, old_cargo_capacity);
+
+        {
+            let f = self.fleets.get_mut(&new_id).unwrap();
+            f.current_fuel = split_fuel;
+            f.cargo.ironium = split_ironium;
+            f.cargo.boranium = split_boranium;
+            f.cargo.germanium = split_germanium;
+            f.cargo.colonists = split_colonists;
+            f.orders = orders;
+            f.repeat_orders = repeat_orders;
+            f.members[0].damage = split_damage;
+            f.battle_plan = battle_plan;
+        }
+
+        {
+            let f = self.fleets.get_mut(&fleet_id).unwrap();
+            f.current_fuel -= split_fuel;
+            f.cargo.ironium -= split_ironium;
+            f.cargo.boranium -= split_boranium;
+            f.cargo.germanium -= split_germanium;
+            f.cargo.colonists -= split_colonists;
+        }
+        self.recalculate_fleet_capacities(fleet_id);
+
+        for p in self.planets.iter_mut() {
+            if p.location.is_same_location(&location) {
+                p.related_fleets.push(new_id);
+            }
+        }
+
+        Some(new_id)
+    }
+
+    /*
+        Gives a fleet to another player.  The recipient receives copies of
+        any designs in the fleet they do not already have, so they need
+        enough free design slots.  Fleets carrying colonists cannot be given
+        away.
+    */
+    pub fn transfer_fleet(&mut self, player_id: u8, fleet_id: u32, to_player_id: u8) -> bool {
+        let (name, members, colonists) = match self.fleets.get(&fleet_id) {
+            Some(f) if f.owner_id == Some(player_id) => (f.name.clone(), f.members.clone(), f.cargo.colonists),
+            _ => {
+                error!("Player {} tried to give away fleet {} which they do not own", player_id, fleet_id);
+                return false;
+            }
+        };
+
+        if to_player_id == player_id || to_player_id as usize >= self.players.len() {
+            error!("Player {} tried to give fleet {} to invalid player {}", player_id, fleet_id, to_player_id);
+            return false;
+        }
+
+        if colonists > 0 {
+            self.players[player_id as usize].add_message(MessageId::FleetTransferHasColonists, vec![name]);
+            return false;
+        }
+
+        let giver_name = self.players[player_id as usize].race.plural_name.clone();
+        let recipient_name = self.players[to_player_id as usize].race.plural_name.clone();
+
+        // One entry per member, so that a missing design does not shift the
+        // rest of the stacks onto the wrong designs
+        let designs : Vec<Option<ShipDesign>> = members.iter().map(|m| self.lookup_ship_design(m.design_id)).collect();
+        let needed_slots = designs.iter()
+            .flatten()
+            .filter(|d| self.players[to_player_id as usize].find_matching_design(d).is_none())
+            .count();
+
+        if needed_slots > self.players[to_player_id as usize].free_ship_design_slots() {
+            self.players[player_id as usize].add_message(MessageId::FleetTransferNoDesignCapacity, vec![name, recipient_name]);
+            self.players[to_player_id as usize].add_message(MessageId::FleetTransferRecipientNoDesignCapacity, vec![giver_name]);
+            return false;
+        }
+
+        let mut new_members = Vec::new();
+        {
+            let recipient = &mut self.players[to_player_id as usize];
+            for (m, d) in members.iter().zip(designs.iter()) {
+                let d = match d {
+                    Some(d) => d,
+                    None => {
+                        new_members.push(m.clone());
+                        continue;
+                    }
+                };
+
+                let design_id = match recipient.find_matching_design(d) {
+                    Some(id) => id,
+                    None => {
+                        let mut copy = d.clone();
+                        copy.is_obsolete = false;
+                        let index = recipient.add_ship_design(copy);
+                        recipient.ship_designs[index as usize].as_ref().unwrap().id
+                    }
+                };
+
+                new_members.push(FleetMember {
+                    design_id,
+                    quantity: m.quantity,
+                    damage: m.damage
+                });
+            }
+        }
+
+        {
+            let f = self.fleets.get_mut(&fleet_id).unwrap();
+            f.owner_id = Some(to_player_id);
+            f.members = new_members;
+            f.battle_plan = 0;
+            f.repeat_orders = false;
+            f.orders.truncate(1);
+            f.orders[0].task = ShipOrderType::NoTask;
+        }
+
+        self.players[player_id as usize].add_message(MessageId::FleetTransferred, vec![name.clone(), recipient_name]);
+        self.players[to_player_id as usize].add_message(MessageId::FleetReceived, vec![giver_name, name]);
+        true
+    }
+
+    /*
+        Moves cargo between two fleets at the same location.  Cargo may be
+        given to any fleet, but colonists can only move between fleets of
+        the same player, and cargo can only be taken from one's own fleets.
+    */
+    pub fn transfer_cargo(&mut self, player_id: u8, fleet_id: u32, target_fleet_id: u32, transfers: &[CargoTransfer]) -> bool {
+        let (name, target_owner) = match (self.fleets.get(&fleet_id), self.fleets.get(&target_fleet_id)) {
+            (Some(a), Some(b)) if a.owner_id == Some(player_id) && a.location.is_same_location(&b.location) && fleet_id != target_fleet_id => {
+                (a.name.clone(), b.owner_id)
+            },
+            _ => {
+                error!("Player {} cannot transfer cargo from fleet {} to fleet {}", player_id, fleet_id, target_fleet_id);
+                return false;
+            }
+        };
+
+        for t in transfers.iter() {
+            if t.cargo_type == CargoType::Colonists && target_owner != Some(player_id) {
+                self.players[player_id as usize].add_message(MessageId::ColonistTransferToPlayerRefused, vec![name.clone()]);
+                continue;
+            }
+
+            let (carried, target_carried, target_capacity, target_free) = {
+                let target = &self.fleets[&target_fleet_id];
+                (self.fleets[&fleet_id].cargo.get(t.cargo_type), target.cargo.get(t.cargo_type), target.total_cargo_capacity, target.free_cargo_space())
+            };
+
+            let requested = match t.amount {
+                TransferAmount::Amount(a) => a,
+                TransferAmount::All => carried,
+                TransferAmount::FillPercent(percent) => {
+                    (target_capacity * (percent.min(100) as u32) / 100).saturating_sub(target_carried)
+                }
+            };
+
+            let amount = requested.min(carried).min(target_free);
+            *self.fleets.get_mut(&fleet_id).unwrap().cargo.get_mut(t.cargo_type) -= amount;
+            *self.fleets.get_mut(&target_fleet_id).unwrap().cargo.get_mut(t.cargo_type) += amount;
+        }
+
+        true
+    }
+
+    pub fn remove_fleet(&mut self, fleet_id: u32) -> Option<Fleet> {
+        for p in self.planets.iter_mut() {
+            p.related_fleets.retain(|id| *id != fleet_id);
+        }
+
+        self.fleets.remove(&fleet_id)
+    }
+
+    /*
+        Removes one of a player's battle plans.  Fleets that used it fall
+        back to the default plan, and later plans move down one place.
+    */
+    pub fn delete_battle_plan(&mut self, player_id: u8, index: u8) -> bool {
+        if index == 0 || index as usize >= self.players[player_id as usize].battle_plans.len() {
+            error!("Player {} cannot delete battle plan {}", player_id, index);
+            return false;
+        }
+
+        self.players[player_id as usize].battle_plans.remove(index as usize);
+
+        for f in self.fleets.values_mut().filter(|f| f.owner_id == Some(player_id)) {
+            if f.battle_plan == index {
+                f.battle_plan = 0;
+            }
+            else if f.battle_plan > index {
+                f.battle_plan -= 1;
+            }
+        }
+
+        true
+    }
+
+    pub fn set_fleet_battle_plan(&mut self, player_id: u8, fleet_id: u32, index: u8) -> bool {
+        if index as usize >= self.players[player_id as usize].battle_plans.len() {
+            error!("Player {} has no battle plan {}", player_id, index);
+            return false;
+        }
+
+        match self.fleets.get_mut(&fleet_id) {
+            Some(f) if f.owner_id == Some(player_id) => {
+                f.battle_plan = index;
+                true
+            },
+            _ => {
+                error!("Player {} tried to set the battle plan of fleet {} which they do not own", player_id, fleet_id);
+                false
+            }
+        }
+    }
+
+    pub fn set_player_relation(&mut self, player_id: u8, other_player_id: u8, relation: PlayerRelation) -> bool {
+        if player_id == other_player_id || other_player_id as usize >= self.players.len() {
+            error!("Player {} cannot set a relation with player {}", player_id, other_player_id);
+            return false;
+        }
+
+        self.players[player_id as usize].set_relation(other_player_id, relation);
+        true
+    }
+
+    /*
+        Spreads damage over a fleet's stacks by ship count.  A stack's damage
+        adds to what it already carries, and it loses one whole ship for
+        every full armor's worth of damage; what is left over stays on the
+        surviving ships.  The fleet is removed once no ships are left.
+        Returns the number of ships destroyed.
+    */
+    pub fn apply_fleet_damage(&mut self, fleet_id: u32, damage: u32) -> u32 {
+        let members = match self.fleets.get(&fleet_id) {
+            Some(f) => f.members.clone(),
+            None => return 0
+        };
+
+        let ships = members.iter().map(|m| m.quantity as u32).sum::<u32>();
+        if ships == 0 || damage == 0 {
+            return 0;
+        }
+
+        let mut destroyed = 0;
+        let mut remaining = Vec::new();
+        for m in members.into_iter() {
+            let armor = self.lookup_ship_design(m.design_id).map_or(1, |d| d.calculate_armor().max(1));
+            let total = m.damage + damage * m.quantity as u32 / ships;
+            let lost = (total / armor).min(m.quantity as u32) as u16;
+
+            destroyed += lost as u32;
+            if lost < m.quantity {
+                remaining.push(FleetMember { design_id: m.design_id, quantity: m.quantity - lost, damage: total - lost as u32 * armor });
+            }
+        }
+
+        if remaining.is_empty() {
+            self.remove_fleet(fleet_id);
+        }
+        else {
+            self.fleets.get_mut(&fleet_id).unwrap().members = remaining;
+            self.recalculate_fleet_capacities(fleet_id);
+        }
+
+        destroyed
+    }
+
     pub fn add_fleet_at_planet(&mut self, design: &ShipDesign, owner: Option<u8>, planet_id: u32, quantity: u16) -> u32 {
-        let location = self.planets[planet_id as usize].location.clone();
+        let location = self.get_planet(planet_id).unwrap().location.clone();
         let fleet_id = self.add_fleet(design, owner, location, quantity);
 
-        self.planets[planet_id as usize].related_fleets.push(fleet_id);
+        self.fleets.get_mut(&fleet_id).unwrap().orders[0].target = WaypointTarget::Planet(planet_id);
+        self.get_planet_mut(planet_id).unwrap().related_fleets.push(fleet_id);
         return fleet_id;
     }
 
+    /*
+        Replaces a fleet's waypoints.  The fleet's current position is always
+        kept first, so the given list starts with the next destination.  Waypoint locations are taken from their targets.
+    */
+    pub fn set_fleet_waypoints(&mut self, player_id: u8, fleet_id: u32, waypoints: Vec<Waypoint>, repeat_orders: bool) -> bool {
+        match self.fleets.get(&fleet_id) {
+            Some(f) if f.owner_id == Some(player_id) => {},
+            _ => {
+                error!("Player {} tried to give orders to fleet {} which they do not own", player_id, fleet_id);
+                return false;
+            }
+        }
+
+        let f = &self.fleets[&fleet_id];
+        let mut orders = Vec::new();
+        if f.orders[0].location.is_same_location(&f.location) {
+            orders.push(f.orders[0].clone());
+        }
+        else {
+            orders.push(Waypoint::construct(WaypointTarget::Space, f.location.clone(), 0, ShipOrderType::NoTask));
+        }
+
+        for mut w in waypoints {
+            match self.locate_waypoint_target(&w.target) {
+                Some(location) => w.location = location,
+                None => {
+                    if w.target != WaypointTarget::Space {
+                        error!("Player {} sent fleet {} to an unknown target", player_id, fleet_id);
+                        return false;
+                    }
+                }
+            }
+
+            orders.push(w);
+        }
+
+        let f = self.fleets.get_mut(&fleet_id).unwrap();
+        f.orders = orders;
+        f.repeat_orders = repeat_orders;
+        true
+    }
+
+    pub fn locate_waypoint_target(&self, target: &WaypointTarget) -> Option<SpaceCoordinate> {
+        match *target {
+            WaypointTarget::Planet(pid) => self.get_planet(pid).map(|p| p.location.clone()),
+            WaypointTarget::Fleet(fid) => self.fleets.get(&fid).map(|f| f.location.clone()),
+            WaypointTarget::Wormhole(wid) => self.wormholes.iter().find(|w| w.id == wid).map(|w| w.location.clone()),
+            WaypointTarget::MysteryTrader(tid) => self.mystery_traders.iter().find(|t| t.id == tid).map(|t| t.location.clone()),
+            WaypointTarget::Space => None
+        }
+    }
+
+    /*
+        A fleet is visible to a player if the player owns it, if it orbits
+        one of the player's planets, or if it is within scanner range of one
+        of the player's fleets.
+    */
+    pub fn is_fleet_visible_to(&self, player_id: u8, fleet_id: u32) -> bool {
+        let target = match self.fleets.get(&fleet_id) {
+            Some(f) => f,
+            None => return false
+        };
+
+        if target.owner_id == Some(player_id) {
+            return true;
+        }
+
+        let at_owned_planet = self.planets.iter()
+            .any(|p| p.owner_id == Some(player_id) && p.location.is_same_location(&target.location));
+        if at_owned_planet {
+            return true;
+        }
+
+        self.fleets.values()
+            .filter(|f| f.owner_id == Some(player_id))
+            .any(|f| f.location.distance_to(&target.location) <= self.fleet_scanner_range(f.id) as f64)
+    }
+
+    pub fn fleet_scanner_range(&self, fleet_id: u32) -> u16 {
+        match self.fleets.get(&fleet_id) {
+            Some(f) => f.members.iter()
+                .filter_map(|m| self.lookup_ship_design(m.design_id))
+                .map(|d| d.scanner_range())
+                .max()
+                .unwrap_or(0),
+            None => 0
+        }
+    }
+
+    /*
+        Places a starbase in orbit of a planet, replacing any starbase that
+        was already there.
+    */
+    pub fn add_starbase_at_planet(&mut self, design: &ShipDesign, owner: u8, planet_id: u32) -> u32 {
+        let id = self.get_new_starbase_id();
+        let starbase = Starbase::construct(id, owner, planet_id, design);
+
+        let previous = match self.get_planet_mut(planet_id) {
+            Some(p) => p.related_starbase.replace(id),
+            None => {
+                error!("Tried to add a starbase to unknown planet {}", planet_id);
+                return id;
+            }
+        };
+
+        if let Some(old_id) = previous {
+            self.starbases.remove(&old_id);
+        }
+
+        self.starbases.insert(id, starbase);
+        id
+    }
+
+    pub fn remove_starbase(&mut self, starbase_id: u32) -> Option<Starbase> {
+        let starbase = self.starbases.remove(&starbase_id)?;
+
+        if let Some(p) = self.get_planet_mut(starbase.planet_id) {
+            if p.related_starbase == Some(starbase_id) {
+                p.related_starbase = None;
+            }
+        }
+
+        Some(starbase)
+    }
+
+    pub fn get_planet_starbase(&self, planet_id: u32) -> Option<&Starbase> {
+        match self.get_planet(planet_id) {
+            Some(p) => match p.related_starbase {
+                Some(sid) => self.starbases.get(&sid),
+                None => None
+            },
+            None => None
+        }
+    }
+
+    /*
+        Warp rating of the mass driver on the planet's starbase, if it has
+        one.
+    */
+    pub fn planet_mass_driver_warp(&self, planet_id: u32) -> Option<u8> {
+        let starbase = self.get_planet_starbase(planet_id)?;
+        let design = self.lookup_ship_design(starbase.design_id)?;
+
+        design.filled_slots().iter()
+            .filter_map(|s| TECHNOLOGY_DETAILS[s.tid as usize].warp)
+            .max()
+    }
+
+    /*
+        Safe mass and safe distance of the stargate on the planet's
+        starbase, if it has one.  STARGATE_INFINITE_VALUE means no limit.
+    */
+    pub fn planet_stargate(&self, planet_id: u32) -> Option<(u16, u16)> {
+        let starbase = self.get_planet_starbase(planet_id)?;
+        let design = self.lookup_ship_design(starbase.design_id)?;
+
+        design.filled_slots().iter()
+            .map(|s| &TECHNOLOGY_DETAILS[s.tid as usize])
+            .filter_map(|t| Some((t.safe_mass?, t.safe_distance?)))
+            .next()
+    }
+
+    /*
+        Targets a planet's mass driver at another planet.  Packets are flung
+        at the given warp, no faster than the driver is rated for.  A
+        destination of None stops the driver.
+    */
+    pub fn set_packet_destination(&mut self, player_id: u8, planet_id: u32, destination_id: Option<u32>, warp: u8) -> bool {
+        let rating = match self.get_planet(planet_id) {
+            Some(p) if p.owner_id == Some(player_id) => self.planet_mass_driver_warp(planet_id),
+            _ => return false
+        };
+
+        if let Some(did) = destination_id {
+            if did == planet_id || self.get_planet(did).is_none() {
+                return false;
+            }
+        }
+
+        let warp = match (destination_id, rating) {
+            (None, _) => None,
+            (Some(_), Some(max_warp)) => Some(warp.clamp(1, max_warp)),
+            (Some(_), None) => return false
+        };
+
+        let p = self.get_planet_mut(planet_id).unwrap();
+        p.packet_destination_id = destination_id;
+        p.packet_warpspeed = warp;
+        true
+    }
+
+    /*
+        Flings a packet of surface minerals from a planet at its mass
+        driver's destination.  The owner of the destination is warned when
+        their own driver is too slow to catch the packet safely.
+    */
+    pub fn launch_packet(&mut self, player_id: u8, planet_id: u32, ironium: u16, boranium: u16, germanium: u16) -> bool {
+        let (name, location, destination_id, warp, on_surface) = match self.get_planet(planet_id) {
+            Some(p) if p.owner_id == Some(player_id) => (p.name.clone(), p.location.clone(), p.packet_destination_id, p.packet_warpspeed, (p.on_surface.ironium, p.on_surface.boranium, p.on_surface.germanium)),
+            _ => return false
+        };
+
+        if self.planet_mass_driver_warp(planet_id).is_none() {
+            self.players[player_id as usize].add_message(MessageId::PacketNoMassDriver, vec![name]);
+            return false;
+        }
+
+        let (destination_id, warp) = match (destination_id, warp) {
+            (Some(d), Some(w)) => (d, w),
+            _ => {
+                self.players[player_id as usize].add_message(MessageId::PacketNoDestination, vec![name]);
+                return false;
+            }
+        };
+
+        if ironium > on_surface.0 || boranium > on_surface.1 || germanium > on_surface.2 || ironium as u32 + boranium as u32 + germanium as u32 == 0 {
+            self.players[player_id as usize].add_message(MessageId::PacketNotEnoughMinerals, vec![name]);
+            return false;
+        }
+
+        let (destination_name, destination_location, destination_owner) = {
+            let d = self.get_planet(destination_id).unwrap();
+            (d.name.clone(), d.location.clone(), d.owner_id)
+        };
+
+        {
+            let p = self.get_planet_mut(planet_id).unwrap();
+            p.on_surface.ironium -= ironium;
+            p.on_surface.boranium -= boranium;
+            p.on_surface.germanium -= germanium;
+        }
+
+        let id = self.mineral_packets.iter().map(|mp| mp.id + 1).max().unwrap_or(0);
+        self.mineral_packets.push(MineralPacket {
+            id,
+            location,
+            heading: Heading { warp, next_location: destination_location },
+            owner_id: player_id,
+            destination_id,
+            minerals: MineralContents { ironium, boranium, germanium }
+        });
+
+        self.players[player_id as usize].add_message(MessageId::PacketLaunched, vec![name, destination_name.clone()]);
+
+        if let Some(owner_id) = destination_owner {
+            if self.planet_mass_driver_warp(destination_id).unwrap_or(0) < warp {
+                self.players[owner_id as usize].add_message(MessageId::PacketIncoming, vec![destination_name]);
+            }
+        }
+
+        true
+    }
+
+    /*
+        Adds a design to a planet's production queue.
+
+        Starbase designs may be queued at any owned planet, since they are
+        constructed in orbit.  Ships need a starbase whose dock can hold the
+        hull; planets with no starbase cannot build ships at all.
+    */
+    pub fn queue_ship_build(&mut self, player_id: u8, planet_id: u32, design_id: u32, quantity: u16) -> bool {
+        let design = match self.players.get(player_id as usize).and_then(|p| p.lookup_active_design(design_id)) {
+            Some(d) => d.clone(),
+            None => {
+                error!("Player {} tried to build unknown design {}", player_id, design_id);
+                return false;
+            }
+        };
+
+        if quantity == 0 {
+            error!("Player {} tried to build no ships of design {}", player_id, design_id);
+            return false;
+        }
+
+        match self.get_planet(planet_id) {
+            Some(p) => {
+                if p.owner_id != Some(player_id) {
+                    error!("Player {} tried to build at planet {} which they do not own", player_id, planet_id);
+                    return false;
+                }
+            },
+            None => {
+                error!("Player {} tried to build at unknown planet {}", player_id, planet_id);
+                return false;
+            }
+        }
+
+        if !design.is_starbase() {
+            let dock_ok = match self.get_planet_starbase(planet_id) {
+                Some(sb) => match self.lookup_ship_design(sb.design_id) {
+                    Some(sb_design) => sb.can_build(&sb_design, &design),
+                    None => false
+                },
+                None => false
+            };
+
+            if !dock_ok {
+                info!("Planet {} has no dock able to build design {}", planet_id, design_id);
+                return false;
+            }
+        }
+
+        let cost = design.calculate_cost();
+        let p = self.get_planet_mut(planet_id).unwrap();
+        p.production_queue.push(BuildItem {
+            quantity,
+            percent_complete: 0,
+            design_id,
+            required_resources: cost.resources,
+            required_minerals: MineralContents {
+                ironium: cost.ironium.min(u16::MAX as u32) as u16,
+                boranium: cost.boranium.min(u16::MAX as u32) as u16,
+                germanium: cost.germanium.min(u16::MAX as u32) as u16
+            },
+            estimated_completion_year: None
+        });
+
+        true
+    }
+
     pub fn add_fleet(&mut self, design: &ShipDesign, owner: Option<u8>, location: SpaceCoordinate, quantity: u16) -> u32 {
         let id : u32 = self.get_new_fleet_id();
         let mut initial_orders = Vec::new();
-        initial_orders.push(ShipOrder {
-            order_type: ShipOrderType::NoTask,
-            amount: None
-        });
+        initial_orders.push(Waypoint::construct(WaypointTarget::Space, location.clone(), 0, ShipOrderType::NoTask));
 
         let mut members = Vec::new();
         members.push(FleetMember {
             design_id: design.id,
-            quantity: quantity
+            quantity: quantity,
+            damage: 0
         });
 
         let total_fuel = Fleet::calculate_total_fuel_capacity(self, &members);
+        let total_cargo = Fleet::calculate_total_cargo_capacity(self, &members);
 
         let f = Fleet {
             id: id,
+            name: format!("{} #{}", design.name, id),
             owner_id: owner,
             location: location,
-            heading: None,
-            warp: None,
             current_fuel: total_fuel.clone(),
             total_fuel_capacity: total_fuel.clone(),
+            cargo: Cargo::default(),
+            total_cargo_capacity: total_cargo,
             repeat_orders: false,
             orders: initial_orders,
-            members: members
+            members: members,
+            battle_plan: 0
         };
 
         let key = f.id.clone();
@@ -349,7 +1420,15 @@ impl Universe {
         return ret;
     }
 
-    pub fn generate_initial_ships(&mut self) {
+    /*
+        Starting fleets follow the original game: every race gets a colony
+        ship and a scout, the primary racial trait adds its own ships (Space
+        Demolition its mine layers), Advanced Remote Mining adds a Potato Bug,
+        and accelerated play grants one extra colonizer.  Lesser racial
+        traits also pick the engines the designs are built with.  Each
+        starting design becomes its own fleet.
+    */
+    pub fn generate_initial_ships(&mut self, accelerated_play: bool) {
         let mut ship_queue = Vec::new();
 
         for p in self.players.iter_mut() {
@@ -360,64 +1439,53 @@ impl Universe {
             let best_miner = p.get_best_starting_miner();
             let ship_designs = construct_initial_ship_designs(best_engine, best_laser, best_shield, best_scanner, best_miner);
 
-            match p.race.primary_racial_trait {
-                PrimaryRacialTrait::ClaimAdjuster => {
-                    //p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                    //ship_queue.push((p.ship_designs[0].clone().unwrap(), p.id, p.homeworld_id, 1));
-                },
-                PrimaryRacialTrait::JackOfAllTrades => {
-                    p.add_ship_design(ship_designs[ShipId::ArmedProbe as usize].clone());
-                    p.add_ship_design(ship_designs[ShipId::LongRangeScout as usize].clone());
-                    p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                    p.add_ship_design(ship_designs[ShipId::Teamster as usize].clone());
-                    p.add_ship_design(ship_designs[ShipId::StalwartDefender as usize].clone());
-                    p.add_ship_design(ship_designs[ShipId::CottonPicker as usize].clone());
-
-                    ship_queue.push((p.ship_designs[0].clone().unwrap(), p.id, p.homeworld_id, 1));
-                    ship_queue.push((p.ship_designs[1].clone().unwrap(), p.id, p.homeworld_id, 1));
-                    ship_queue.push((p.ship_designs[2].clone().unwrap(), p.id, p.homeworld_id, 1));
-                    ship_queue.push((p.ship_designs[3].clone().unwrap(), p.id, p.homeworld_id, 1));
-                    ship_queue.push((p.ship_designs[4].clone().unwrap(), p.id, p.homeworld_id, 1));
-                    ship_queue.push((p.ship_designs[5].clone().unwrap(), p.id, p.homeworld_id, 1));
-                }
-    
-                PrimaryRacialTrait::InterstellarTraveler => {
-                    /*
-                    *p.add_ship_design(ship_designs[ShipId::SmaugarianPeepingTom as usize].clone());
-                    *p.add_ship_design(ship_designs[ShipId::Mayflower as usize].clone());
-                    *p.add_ship_design(ship_designs[ShipId::StalwartDefender as usize].clone());
-                    *p.add_ship_design(ship_designs[ShipId::Swashbuckler as usize].clone());
-                    */
-                },
-                PrimaryRacialTrait::InnerStrength => {
-                    //*p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                },
-                PrimaryRacialTrait::SpaceDemolition => {
-                    //*p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                },
-                PrimaryRacialTrait::WarMonger => {
-                    //*p.add_ship_design(ship_designs[ShipId::ArmedProbe as usize].clone());
-                    //*p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                },
-                PrimaryRacialTrait::PacketPhysics => {
-                    //*p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                    //*p.add_ship_design(ship_designs[ShipId::LongRangeScout as usize].clone());
-                },
-                PrimaryRacialTrait::SuperStealth => {
-                    //*p.add_ship_design(ship_designs[ShipId::SantaMaria as usize].clone());
-                    //*p.add_ship_design(ship_designs[ShipId::ShadowTransport as usize].clone());
-                },
-                PrimaryRacialTrait::HyperExpansion => {
-                    //*p.add_ship_design(ship_designs[ShipId::SporeCloud as usize].clone());
-                },
-                PrimaryRacialTrait::AlternateReality => {
-                    //(&p).add_ship_design(ship_designs[ShipId::Pinta as usize].clone());
-                }
+            let prt = &p.race.primary_racial_trait;
+            let colonizer = match *prt {
+                PrimaryRacialTrait::InterstellarTraveler => ShipId::Mayflower,
+                PrimaryRacialTrait::HyperExpansion => ShipId::SporeCloud,
+                PrimaryRacialTrait::AlternateReality => ShipId::Pinta,
+                _ => ShipId::SantaMaria
+            };
+            let scout = match *prt {
+                PrimaryRacialTrait::InterstellarTraveler => ShipId::SmaugarianPeepingTom,
+                _ => ShipId::LongRangeScout
+            };
+
+            let mut starting_ships = vec![(colonizer, 1), (scout, 1)];
+            starting_ships.extend(match *prt {
+                PrimaryRacialTrait::JackOfAllTrades => vec![
+                    (ShipId::ArmedProbe, 1),
+                    (ShipId::Teamster, 1),
+                    (ShipId::StalwartDefender, 1),
+                    (ShipId::CottonPicker, 1)
+                ],
+                PrimaryRacialTrait::InterstellarTraveler => vec![
+                    (ShipId::StalwartDefender, 1),
+                    (ShipId::Swashbuckler, 1)
+                ],
+                PrimaryRacialTrait::SpaceDemolition => vec![
+                    (ShipId::LittleHen, 2)
+                ],
+                PrimaryRacialTrait::WarMonger => vec![
+                    (ShipId::ArmedProbe, 1)
+                ],
+                PrimaryRacialTrait::SuperStealth => vec![
+                    (ShipId::ShadowTransport, 1)
+                ],
+                _ => Vec::new()
+            });
+
+            if accelerated_play {
+                starting_ships[0].1 += 1;
             }
 
             if p.race.lesser_racial_traits.contains(&LesserRacialTrait::AdvancedRemoteMining) {
-                let index = p.add_ship_design(ship_designs[ShipId::PotatoBug as usize].clone());
-                ship_queue.push((p.ship_designs[index as usize].clone().unwrap(), p.id, p.homeworld_id, 1));
+                starting_ships.push((ShipId::PotatoBug, 1));
+            }
+
+            for (ship_id, quantity) in starting_ships {
+                let index = p.add_ship_design(ship_designs[ship_id as usize].clone());
+                ship_queue.push((p.ship_designs[index as usize].clone().unwrap(), p.id, p.homeworld_id, quantity));
             }
         }
 
@@ -426,4 +1494,290 @@ impl Universe {
             self.add_fleet_at_planet(&design, Some(owner_id), homeworld_id, quantity);
         }
     }
+
+    pub fn generate_homeworld_installations(&mut self, accelerated_play: bool) {
+        for player in self.players.iter() {
+            match self.planets.iter_mut().find(|p| p.id == player.homeworld_id) {
+                Some(p) => {
+                    p.setup_homeworld_installations(&player.race, accelerated_play);
+                    p.defense_quality = player.get_best_defense_quality();
+                },
+                None => error!("Player {} has no homeworld {}", player.id, player.homeworld_id)
+            }
+        }
+    }
+
+    /*
+        Every player begins with a Space Station orbiting their homeworld.
+        Interstellar Travelers instead start with the Portal to Nowhere, a
+        station that also carries a 100/250 stargate.
+    */
+    pub fn generate_initial_starbases(&mut self) {
+        let mut starbase_queue = Vec::new();
+
+        for p in self.players.iter_mut() {
+            let best_laser = p.get_best_starting_laser();
+            let best_shield = p.get_best_starting_shield();
+            let starbase_designs = construct_initial_starbase_designs(best_laser, best_shield);
+
+            let starter = match p.race.primary_racial_trait {
+                PrimaryRacialTrait::InterstellarTraveler => StarbaseId::PortalToNowhere,
+                _ => StarbaseId::SpaceStation
+            };
+
+            let index = p.add_starbase_design(starbase_designs[starter as usize].clone());
+            starbase_queue.push((p.starbase_designs[index as usize].clone().unwrap(), p.id, p.homeworld_id));
+        }
+
+        for q in starbase_queue {
+            let (design, owner_id, homeworld_id) = q;
+            self.add_starbase_at_planet(&design, owner_id, homeworld_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::game::objects::player::Player;
+    use ::game::objects::order::PlayerOrder;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::objects::fleet::ShipSlot;
+    use ::game::objects::race::Race;
+    use ::game::objects::tech::TechnologyId;
+    use ::game::objects::tech::TechnologyCategory;
+    use ::game::objects::tech::MYSTERY_TRADER_TECHNOLOGY;
+    use ::game::tutorial::generate_tutorial_universe;
+
+    // The tutorial map with a single player of the given race, whose
+    // homeworld is the map's first planet
+    fn lone_player_universe(race: Race) -> Universe {
+        let mut player = Player::construct_from_race(race);
+        player.id = 0;
+
+        let mut u = generate_tutorial_universe();
+        u.planets[0].set_homeworld(&mut player);
+        u.players.push(player);
+        u
+    }
+
+    fn all_primary_racial_traits() -> Vec<PrimaryRacialTrait> {
+        vec![
+            PrimaryRacialTrait::HyperExpansion,
+            PrimaryRacialTrait::SuperStealth,
+            PrimaryRacialTrait::WarMonger,
+            PrimaryRacialTrait::ClaimAdjuster,
+            PrimaryRacialTrait::InnerStrength,
+            PrimaryRacialTrait::SpaceDemolition,
+            PrimaryRacialTrait::PacketPhysics,
+            PrimaryRacialTrait::InterstellarTraveler,
+            PrimaryRacialTrait::AlternateReality,
+            PrimaryRacialTrait::JackOfAllTrades
+        ]
+    }
+
+    /*
+        Generates the starting ships for a lone Humanoid player with the
+        given traits and returns each stack's design and size.
+    */
+    fn starting_fleet(prt: PrimaryRacialTrait, lrts: Vec<LesserRacialTrait>, accelerated_play: bool) -> Vec<(ShipDesign, u16)> {
+        let mut race = create_humanoid();
+        race.primary_racial_trait = prt;
+        race.lesser_racial_traits = lrts;
+
+        let mut u = lone_player_universe(race);
+        let homeworld = u.planets[0].location.clone();
+
+        u.generate_initial_ships(accelerated_play);
+
+        let mut ships = Vec::new();
+        for f in u.fleets.values() {
+            assert_eq!(f.owner_id, Some(0));
+            assert_eq!((f.location.x, f.location.y), (homeworld.x, homeworld.y));
+
+            for m in f.members.iter() {
+                ships.push((u.players[0].lookup_design(m.design_id).unwrap().clone(), m.quantity));
+            }
+        }
+        ships
+    }
+
+    fn has_part(design: &ShipDesign, category: TechnologyCategory) -> bool {
+        design.filled_slots().iter().any(|s| s.tid.category() == category)
+    }
+
+    fn is_colonizer(design: &ShipDesign) -> bool {
+        design.count_slot_technology(TechnologyId::ColonizationModule) > 0
+            || design.count_slot_technology(TechnologyId::OrbitalConstructionModule) > 0
+    }
+
+    fn count_ships(ships: &[(ShipDesign, u16)], test: fn(&ShipDesign) -> bool) -> u16 {
+        ships.iter().filter(|s| test(&s.0)).map(|s| s.1).sum()
+    }
+
+    #[test]
+    fn every_race_starts_with_one_colonizer_and_a_scout() {
+        for prt in all_primary_racial_traits() {
+            let ships = starting_fleet(prt, Vec::new(), false);
+
+            assert_eq!(count_ships(&ships, is_colonizer), 1);
+            assert!(count_ships(&ships, |d| has_part(d, TechnologyCategory::Scanner) && !d.is_armed()) >= 1);
+        }
+    }
+
+    #[test]
+    fn starting_designs_fit_their_hulls() {
+        for prt in all_primary_racial_traits() {
+            for (design, _) in starting_fleet(prt, vec![LesserRacialTrait::AdvancedRemoteMining], true) {
+                assert!(design.fits_hull(), "{}", design.name);
+            }
+        }
+    }
+
+    #[test]
+    fn accelerated_play_adds_a_colonizer() {
+        for prt in all_primary_racial_traits() {
+            let ships = starting_fleet(prt, Vec::new(), true);
+            assert_eq!(count_ships(&ships, is_colonizer), 2);
+        }
+    }
+
+    #[test]
+    fn space_demolition_starts_with_mine_layers() {
+        let ships = starting_fleet(PrimaryRacialTrait::SpaceDemolition, Vec::new(), false);
+        assert!(count_ships(&ships, |d| has_part(d, TechnologyCategory::MineLayer)) >= 2);
+
+        let ships = starting_fleet(PrimaryRacialTrait::WarMonger, Vec::new(), false);
+        assert_eq!(count_ships(&ships, |d| has_part(d, TechnologyCategory::MineLayer)), 0);
+    }
+
+    #[test]
+    fn war_mongers_start_armed() {
+        let ships = starting_fleet(PrimaryRacialTrait::WarMonger, Vec::new(), false);
+        assert!(count_ships(&ships, |d| d.is_armed()) >= 1);
+
+        let ships = starting_fleet(PrimaryRacialTrait::HyperExpansion, Vec::new(), false);
+        assert_eq!(count_ships(&ships, |d| d.is_armed()), 0);
+    }
+
+    #[test]
+    fn super_stealth_starts_with_a_cloaked_freighter() {
+        let ships = starting_fleet(PrimaryRacialTrait::SuperStealth, Vec::new(), false);
+        assert!(count_ships(&ships, |d| d.calculate_cargo_capacity() > 0 && has_part(d, TechnologyCategory::Electrical)) >= 1);
+    }
+
+    #[test]
+    fn advanced_remote_mining_adds_remote_miners() {
+        let miners = |lrts| count_ships(&starting_fleet(PrimaryRacialTrait::WarMonger, lrts, false), |d| has_part(d, TechnologyCategory::MiningRobot));
+
+        assert_eq!(miners(Vec::new()), 0);
+        assert!(miners(vec![LesserRacialTrait::AdvancedRemoteMining]) >= 1);
+    }
+
+    #[test]
+    fn mystery_trader_parts_fit_their_slots() {
+        // Each trader item with a hull and slot index that holds its kind
+        // of part; the Mini-Morph is itself a hull
+        let placements = [
+            (TechnologyId::Hushaboom, TechnologyId::MiniBomber, 1),
+            (TechnologyId::EnigmaPulsar, TechnologyId::Scout, 0),
+            (TechnologyId::MegaPolyShell, TechnologyId::Destroyer, 3),
+            (TechnologyId::LangstonShell, TechnologyId::Destroyer, 3),
+            (TechnologyId::MultiFunctionPod, TechnologyId::Destroyer, 5),
+            (TechnologyId::AntiMatterTorpedo, TechnologyId::Destroyer, 1),
+            (TechnologyId::JumpGate, TechnologyId::Destroyer, 6),
+            (TechnologyId::GenesisDevice, TechnologyId::SpaceStation, 10),
+            (TechnologyId::MultiContainedMunition, TechnologyId::Destroyer, 2),
+            (TechnologyId::AlienMiner, TechnologyId::MidgetMiner, 1),
+            (TechnologyId::MultiCargoPod, TechnologyId::Destroyer, 6),
+            (TechnologyId::MiniMorph, TechnologyId::MiniMorph, 0)
+        ];
+        assert_eq!(placements.len(), MYSTERY_TRADER_TECHNOLOGY.len());
+
+        let mut u = lone_player_universe(create_humanoid());
+
+        for &(part, hull, slot) in placements.iter() {
+            assert!(MYSTERY_TRADER_TECHNOLOGY.contains(&part));
+
+            let mut slots : [Option<ShipSlot>; 16] = Default::default();
+            if part != hull {
+                slots[slot] = Some(ShipSlot { tid: part, amount: 1 });
+            }
+
+            let design = ShipDesign {
+                id: 0,
+                icon_index: 0,
+                name: "Trader Test".to_string(),
+                base_hull: hull,
+                is_obsolete: false,
+                slots: Some(slots)
+            };
+            assert!(design.fits_hull());
+
+            u.players[0].trader_items = vec![part, hull];
+            assert!(u.add_ship_design(0, design).is_some());
+        }
+    }
+
+    #[test]
+    fn langston_shell_is_armor() {
+        let mut slots : [Option<ShipSlot>; 16] = Default::default();
+        slots[3] = Some(ShipSlot { tid: TechnologyId::LangstonShell, amount: 2 });
+
+        let design = ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Shelled".to_string(),
+            base_hull: TechnologyId::Destroyer,
+            is_obsolete: false,
+            slots: Some(slots)
+        };
+        assert!(design.fits_hull());
+
+        slots = Default::default();
+        slots[5] = Some(ShipSlot { tid: TechnologyId::LangstonShell, amount: 1 });
+        let design = ShipDesign { slots: Some(slots), .. design };
+        assert!(!design.fits_hull());
+    }
+
+    #[test]
+    fn design_orders_only_reach_the_ordering_players_designs() {
+        let mut u = lone_player_universe(create_humanoid());
+        let mut other = Player::construct_from_race(create_humanoid());
+        other.id = 1;
+        u.players.push(other);
+
+        u.players[0].add_ship_design(ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Scout".to_string(),
+            base_hull: TechnologyId::Scout,
+            is_obsolete: false,
+            slots: Some(Default::default())
+        });
+        let design_id = u.players[0].ship_designs[0].as_ref().unwrap().id;
+        let count_designs = |p: &Player| p.ship_designs.iter().filter(|d| d.is_some()).count();
+
+        assert!(!PlayerOrder::RenameShipDesign { design_id, name: "Stolen".to_string() }.execute(&mut u, 1));
+        assert!(!PlayerOrder::ObsoleteShipDesign { design_id, is_obsolete: true }.execute(&mut u, 1));
+        assert!(!PlayerOrder::CopyShipDesign { design_id }.execute(&mut u, 1));
+        assert!(!PlayerOrder::DeleteShipDesign { design_id, scrap_fleets: true }.execute(&mut u, 1));
+        assert_eq!(count_designs(&u.players[1]), 0);
+
+        let design = u.players[0].lookup_design(design_id).unwrap();
+        assert_eq!(design.name, "Scout");
+        assert!(!design.is_obsolete);
+
+        assert!(PlayerOrder::RenameShipDesign { design_id, name: "Renamed".to_string() }.execute(&mut u, 0));
+        assert!(PlayerOrder::ObsoleteShipDesign { design_id, is_obsolete: true }.execute(&mut u, 0));
+        let design = u.players[0].lookup_design(design_id).unwrap();
+        assert_eq!(design.name, "Renamed");
+        assert!(design.is_obsolete);
+
+        assert!(PlayerOrder::CopyShipDesign { design_id }.execute(&mut u, 0));
+        assert_eq!(count_designs(&u.players[0]), 2);
+
+        assert!(PlayerOrder::DeleteShipDesign { design_id, scrap_fleets: false }.execute(&mut u, 0));
+        assert_eq!(count_designs(&u.players[0]), 1);
+    }
 }
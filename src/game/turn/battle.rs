@@ -0,0 +1,1153 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::planet::Planet;
+use ::game::objects::fleet::ShipDesign;
+use ::game::objects::fleet::FleetMember;
+use ::game::objects::tech::TechnologyId;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::player::PlayerRelation;
+use ::game::objects::message::MessageId;
+use ::game::objects::battle::BattleEvent;
+use ::game::objects::battle::BattleRecording;
+use ::game::objects::battle::RecordedToken;
+use ::game::objects::battle::BattlePlan;
+use ::game::objects::battle::BattleTarget;
+use ::game::objects::battle::BattleTactic;
+use ::game::objects::battle::AttackWho;
+
+pub const BATTLE_BOARD_SIZE : i32 = 10;
+pub const MAX_BATTLE_ROUNDS : u32 = 16;
+
+// Beams lose this share of their damage over their full range
+pub const BEAM_DROPOFF_PERCENT : f64 = 10.0;
+
+// Torpedoes that miss still hit the target's shields for this share
+pub const TORPEDO_MISS_SHIELD_FACTOR : f64 = 0.125;
+
+// Capital missiles do this much more damage to unshielded targets
+pub const CAPITAL_MISSILE_FACTOR : u32 = 2;
+
+pub const MIN_BATTLE_MOVEMENT : f64 = 0.5;
+pub const MAX_BATTLE_MOVEMENT : f64 = 2.5;
+
+// Damage per round of a planet's defenses at full coverage
+pub const PLANETARY_DEFENSE_DAMAGE : f64 = 500.0;
+pub const PLANETARY_DEFENSE_RANGE : i32 = 3;
+
+// Squares where each side starts, in order of player id
+const STARTING_SQUARES : [(i32, i32); 8] = [(1, 4), (8, 5), (4, 1), (5, 8), (1, 1), (8, 8), (1, 8), (8, 1)];
+
+#[derive(Clone, PartialEq)]
+pub enum TokenSource {
+    Fleet(u32),
+    Starbase(u32),
+    Planet(u32)
+}
+
+#[derive(Clone)]
+pub struct BattleWeapon {
+    pub tid: TechnologyId,
+    pub count: u32,
+    pub power: u32,
+    pub range: i32,
+    pub initiative: u32,
+    // None for beams
+    pub accuracy: Option<u32>,
+    pub is_spread: bool,
+    pub hits_shields_only: bool,
+    pub is_capital_missile: bool
+}
+
+/*
+    One stack of identical ships (or a starbase) on the battle board.
+    Shields and damage are totals for the whole stack.
+*/
+#[derive(Clone)]
+pub struct BattleToken {
+    pub source: TokenSource,
+    pub owner_id: u8,
+    pub design_id: u32,
+    pub design_name: String,
+    pub quantity: u16,
+    pub starting_quantity: u16,
+    pub armor: u32,
+    pub damage: u32,
+    pub shields_per_ship: u32,
+    pub shields: u32,
+    pub x: i32,
+    pub y: i32,
+    pub movement: f64,
+    pub movement_left: f64,
+    pub weapons: Vec<BattleWeapon>,
+    pub beam_reduction: f64,
+    pub beam_bonus: f64,
+    pub torpedo_bonus: f64,
+    pub jamming: f64,
+    pub plan: BattlePlan,
+    pub is_starbase: bool,
+    pub is_bomber: bool,
+    pub is_fuel_transport: bool,
+    pub is_freighter: bool,
+    // Set once the token has been hit
+    pub challenged: bool
+}
+
+impl BattleToken {
+    pub fn is_alive(&self) -> bool {
+        self.quantity > 0
+    }
+
+    pub fn is_armed(&self) -> bool {
+        !self.weapons.is_empty()
+    }
+
+    pub fn is_planet(&self) -> bool {
+        matches!(self.source, TokenSource::Planet(_))
+    }
+
+    // Planets fire on the ships around them but cannot be fired on
+    pub fn is_targetable(&self) -> bool {
+        self.is_alive() && !self.is_planet()
+    }
+
+    pub fn distance_to(&self, other: &BattleToken) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    pub fn matches(&self, target: BattleTarget) -> bool {
+        match target {
+            BattleTarget::NoTarget => false,
+            BattleTarget::Any => true,
+            BattleTarget::Starbase => self.is_starbase,
+            BattleTarget::Armed => self.is_armed(),
+            BattleTarget::Bomber => self.is_bomber,
+            BattleTarget::Unarmed => !self.is_armed(),
+            BattleTarget::FuelTransport => self.is_fuel_transport,
+            BattleTarget::Freighter => self.is_freighter
+        }
+    }
+
+    /*
+        How close the token tries to get to its target, or None when it
+        wants to get away.
+    */
+    fn desired_range(&self) -> Option<i32> {
+        let longest = self.weapons.iter().map(|w| w.range).max()?;
+        let shortest = self.weapons.iter().map(|w| w.range).min()?;
+
+        match self.plan.tactic {
+            BattleTactic::Disengage => None,
+            BattleTactic::DisengageIfChallenged if self.challenged => None,
+            BattleTactic::DisengageIfChallenged | BattleTactic::MinimizeDamage | BattleTactic::MaximizeDamageRatio => Some(longest),
+            BattleTactic::MaximizeNetDamage => Some(shortest),
+            BattleTactic::MaximizeDamage => Some(0)
+        }
+    }
+
+    fn remaining_armor(&self) -> u32 {
+        (self.armor * self.quantity as u32).saturating_sub(self.damage)
+    }
+
+    /*
+        Damage to shields first unless told otherwise, the rest to armor.
+        Whole ships are lost for each full armor's worth of damage.  Returns
+        the damage absorbed by shields and by armor, so the rest can carry
+        on to another target, and the ships lost.
+    */
+    fn take_damage(&mut self, shield_damage: u32, armor_damage: u32) -> (u32, u32, u16) {
+        let to_shields = shield_damage.min(self.shields);
+        self.shields -= to_shields;
+
+        let to_armor = (armor_damage + shield_damage - to_shields).min(self.remaining_armor());
+        self.damage += to_armor;
+
+        if to_shields + to_armor > 0 {
+            self.challenged = true;
+        }
+
+        let lost = (self.damage / self.armor.max(1)).min(self.quantity as u32) as u16;
+        self.quantity -= lost;
+        self.damage -= lost as u32 * self.armor;
+        self.shields = self.shields.min(self.shields_per_ship * self.quantity as u32);
+
+        (to_shields, to_armor, lost)
+    }
+
+    fn record(&self) -> RecordedToken {
+        RecordedToken {
+            owner_id: self.owner_id,
+            design_id: self.design_id,
+            design_name: self.design_name.clone(),
+            quantity: self.quantity,
+            armor: self.armor,
+            damage: self.damage,
+            shields: self.shields
+        }
+    }
+}
+
+pub struct Battle {
+    pub location: SpaceCoordinate,
+    pub tokens: Vec<BattleToken>,
+    pub rounds: u32,
+    pub events: Vec<BattleEvent>
+}
+
+fn record_hit(events: &mut Vec<BattleEvent>, token: usize, target: usize, weapon: &BattleWeapon, hit: (u32, u32, u16)) {
+    events.push(BattleEvent::Fire {
+        token: token as u16,
+        target: target as u16,
+        weapon: weapon.tid,
+        shield_damage: hit.0,
+        armor_damage: hit.1
+    });
+
+    if hit.2 > 0 {
+        events.push(BattleEvent::Destroy { token: target as u16, ships: hit.2 });
+    }
+}
+
+/*
+    Whether the attacker's battle plan has it open fire on the target's
+    owner.
+*/
+pub fn wants_to_attack(universe: &Universe, attacker: &BattleToken, target: &BattleToken) -> bool {
+    if attacker.owner_id == target.owner_id {
+        return false;
+    }
+
+    let relation = universe.players[attacker.owner_id as usize].relation_to(target.owner_id);
+    match attacker.plan.attack_who {
+        AttackWho::Nobody => false,
+        AttackWho::Enemies => relation == PlayerRelation::Enemy,
+        AttackWho::EnemiesAndNeutrals => relation != PlayerRelation::Friend,
+        AttackWho::Everyone => true,
+        AttackWho::Player(pid) => target.owner_id == pid
+    }
+}
+
+/*
+    Fights a battle wherever hostile players share a location and at least
+    one of them is armed, and returns a recording of each one.
+*/
+pub fn resolve_battles(universe: &mut Universe, rng: &mut StdRng) -> Vec<BattleRecording> {
+    let mut locations : Vec<SpaceCoordinate> = Vec::new();
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        let location = &universe.fleets[&fid].location;
+        if !locations.iter().any(|l| l.is_same_location(location)) {
+            locations.push(location.clone());
+        }
+    }
+
+    let mut battles = Vec::new();
+    for location in locations {
+        let tokens = gather_tokens(universe, &location);
+        if !has_battle(universe, &tokens) {
+            continue;
+        }
+
+        let recorded_tokens = tokens.iter().map(|t| t.record()).collect();
+        let mut battle = Battle { location, tokens, rounds: 0, events: Vec::new() };
+        fight(universe, rng, &mut battle);
+
+        let recording = record_battle(universe, battles.len() as u32, recorded_tokens, &battle);
+        apply_battle_results(universe, &battle);
+        send_battle_messages(universe, &battle);
+
+        for pid in recording.observers.iter() {
+            universe.players[*pid as usize].add_message(MessageId::BattleObserved, vec![recording.location_name.clone()]);
+        }
+        battles.push(recording);
+    }
+
+    battles
+}
+
+/*
+    Players who did not fight can watch a battle if they have a planet
+    there or a fleet with the location in scanner range.
+*/
+fn record_battle(universe: &Universe, id: u32, tokens: Vec<RecordedToken>, battle: &Battle) -> BattleRecording {
+    let mut participants : Vec<u8> = battle.tokens.iter().map(|t| t.owner_id).collect();
+    participants.sort();
+    participants.dedup();
+
+    let mut observers : Vec<u8> = Vec::new();
+    for p in universe.planets.iter().filter(|p| p.location.is_same_location(&battle.location)) {
+        if let Some(owner_id) = p.owner_id {
+            observers.push(owner_id);
+        }
+    }
+    for f in universe.fleets.values() {
+        if let Some(owner_id) = f.owner_id {
+            if f.location.distance_to(&battle.location) <= universe.fleet_scanner_range(f.id) as f64 {
+                observers.push(owner_id);
+            }
+        }
+    }
+    observers.sort();
+    observers.dedup();
+    observers.retain(|o| !participants.contains(o));
+
+    BattleRecording {
+        id,
+        location: battle.location.clone(),
+        location_name: universe.describe_location(&battle.location),
+        participants,
+        observers,
+        tokens,
+        events: battle.events.clone()
+    }
+}
+
+fn gather_tokens(universe: &Universe, location: &SpaceCoordinate) -> Vec<BattleToken> {
+    let mut tokens = Vec::new();
+
+    let mut fleet_ids : Vec<u32> = universe.fleets.iter()
+        .filter(|&(_, f)| f.owner_id.is_some() && f.location.is_same_location(location))
+        .map(|(id, _)| *id)
+        .collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        let f = &universe.fleets[&fid];
+        let owner_id = f.owner_id.unwrap();
+        let plan = universe.players[owner_id as usize].battle_plan(f.battle_plan);
+
+        for m in f.members.iter().filter(|m| m.quantity > 0) {
+            if let Some(design) = universe.lookup_ship_design(m.design_id) {
+                tokens.push(construct_token(TokenSource::Fleet(fid), owner_id, &plan, &design, m.quantity, m.damage));
+            }
+        }
+    }
+
+    if let Some(p) = universe.planets.iter().find(|p| p.location.is_same_location(location)) {
+        if let Some(sb) = universe.get_planet_starbase(p.id) {
+            if let Some(design) = universe.lookup_ship_design(sb.design_id) {
+                let plan = universe.players[sb.owner_id as usize].battle_plan(0);
+                tokens.push(construct_token(TokenSource::Starbase(sb.id), sb.owner_id, &plan, &design, 1, sb.damage));
+            }
+        }
+
+        if let Some(owner_id) = p.owner_id {
+            if p.defenses > 0 {
+                let plan = universe.players[owner_id as usize].battle_plan(0);
+                tokens.push(construct_planet_token(p, owner_id, &plan));
+            }
+        }
+    }
+
+    tokens
+}
+
+/*
+    A battle starts when any armed fleet, starbase or planet wants to attack
+    something there that can be fired on.
+*/
+fn has_battle(universe: &Universe, tokens: &[BattleToken]) -> bool {
+    tokens.iter()
+        .filter(|t| t.is_armed())
+        .any(|t| tokens.iter().any(|o| o.is_targetable() && wants_to_attack(universe, t, o)))
+}
+
+/*
+    Movement per round follows the engine's battle speed, slowed by mass
+    per engine and sped up by overthrusters:
+
+        movement = (speed - 4) / 4 - mass / 280 / engines + modifiers
+
+    and is kept between 1/2 and 2 1/2 squares.  Starbases do not move.
+*/
+fn calculate_battle_movement(design: &ShipDesign) -> f64 {
+    if design.is_starbase() {
+        return 0.0;
+    }
+
+    let engine = match design.engine() {
+        Some(e) => e,
+        None => return 0.0
+    };
+
+    let speed = TECHNOLOGY_DETAILS[engine as usize].battle_speed.unwrap_or(0) as f64;
+    let engines = design.count_slot_technology(engine).max(1) as f64;
+    let modifiers : f64 = design.filled_slots().iter()
+        .filter_map(|s| TECHNOLOGY_DETAILS[s.tid as usize].battle_speed_modifier.map(|m| m as f64 * s.amount as f64))
+        .sum();
+
+    let movement = (speed - 4.0) / 4.0 - design.calculate_mass() as f64 / 280.0 / engines + modifiers;
+    movement.clamp(MIN_BATTLE_MOVEMENT, MAX_BATTLE_MOVEMENT)
+}
+
+fn construct_token(source: TokenSource, owner_id: u8, plan: &BattlePlan, design: &ShipDesign, quantity: u16, damage: u32) -> BattleToken {
+    let hull = &TECHNOLOGY_DETAILS[design.base_hull as usize];
+    let mut initiative = hull.initiative.unwrap_or(0) as u32;
+    let mut beam_reduction = 1.0;
+    let mut beam_bonus = 1.0;
+    let mut torpedo_bonus = 1.0;
+    let mut jamming = 1.0;
+    let mut weapons = Vec::new();
+    let mut is_bomber = false;
+
+    for s in design.filled_slots() {
+        let tech = &TECHNOLOGY_DETAILS[s.tid as usize];
+
+        match tech.power {
+            Some(power) => weapons.push(BattleWeapon {
+                tid: s.tid,
+                count: s.amount as u32,
+                power: power as u32,
+                range: tech.range.unwrap_or(0) as i32,
+                initiative: tech.initiative.unwrap_or(0) as u32,
+                accuracy: tech.accuracy.map(|a| a as u32),
+                is_spread: tech.is_spread == Some(true),
+                hits_shields_only: tech.hits_shields_only == Some(true),
+                is_capital_missile: matches!(s.tid, TechnologyId::JihadMissile | TechnologyId::JuggernautMissile
+                    | TechnologyId::DoomsdayMissile | TechnologyId::ArmageddonMissile)
+            }),
+            None => {
+                // Battle computers: initiative plus better torpedo aim
+                if let Some(i) = tech.initiative {
+                    initiative += i as u32 * s.amount as u32;
+                    if let Some(a) = tech.accuracy {
+                        torpedo_bonus *= (1.0 - a as f64 / 100.0).powi(s.amount as i32);
+                    }
+                }
+            }
+        }
+
+        if tech.colonist_kill_percent.is_some() || tech.buildings_destroyed.is_some() {
+            is_bomber = true;
+        }
+
+        if let Some(r) = tech.beam_reduction {
+            beam_reduction *= (1.0 - r as f64 / 100.0).powi(s.amount as i32);
+        }
+        if let Some(b) = tech.beam_damage {
+            beam_bonus *= (1.0 + b as f64 / 100.0).powi(s.amount as i32);
+        }
+        if let Some(j) = tech.jamming {
+            jamming *= (1.0 - j as f64 / 100.0).powi(s.amount as i32);
+        }
+    }
+
+    for w in weapons.iter_mut() {
+        w.initiative += initiative;
+    }
+
+    let shields_per_ship = design.calculate_shields();
+    let movement = calculate_battle_movement(design);
+
+    BattleToken {
+        source,
+        owner_id,
+        design_id: design.id,
+        design_name: design.name.clone(),
+        quantity,
+        starting_quantity: quantity,
+        armor: design.calculate_armor(),
+        damage,
+        shields_per_ship,
+        shields: shields_per_ship * quantity as u32,
+        x: 0,
+        y: 0,
+        movement,
+        movement_left: 0.0,
+        weapons,
+        beam_reduction,
+        beam_bonus,
+        // Stored as the share of a torpedo's inaccuracy that remains
+        torpedo_bonus,
+        jamming,
+        plan: plan.clone(),
+        is_starbase: design.is_starbase(),
+        is_bomber,
+        is_fuel_transport: design.base_hull == TechnologyId::FuelTransport || design.base_hull == TechnologyId::SuperFuelTransport,
+        is_freighter: design.calculate_cargo_capacity() > 0,
+        challenged: false
+    }
+}
+
+/*
+    A planet's defenses fire as a single beam whose strength follows their
+    coverage:
+
+        power = coverage * 500
+
+    The planet never moves and cannot be hit; bombs are the only way to
+    wear its defenses down.
+*/
+fn construct_planet_token(planet: &Planet, owner_id: u8, plan: &BattlePlan) -> BattleToken {
+    let tid = match planet.defense_quality {
+        Some(ref q) => q.technology(),
+        None => TechnologyId::SDI
+    };
+
+    BattleToken {
+        source: TokenSource::Planet(planet.id),
+        owner_id,
+        design_id: 0,
+        design_name: planet.name.clone(),
+        quantity: 1,
+        starting_quantity: 1,
+        armor: 0,
+        damage: 0,
+        shields_per_ship: 0,
+        shields: 0,
+        x: 0,
+        y: 0,
+        movement: 0.0,
+        movement_left: 0.0,
+        weapons: vec![BattleWeapon {
+            tid,
+            count: 1,
+            power: (planet.defense_coverage() * PLANETARY_DEFENSE_DAMAGE).round() as u32,
+            range: PLANETARY_DEFENSE_RANGE,
+            initiative: 0,
+            accuracy: None,
+            is_spread: false,
+            hits_shields_only: false,
+            is_capital_missile: false
+        }],
+        beam_reduction: 1.0,
+        beam_bonus: 1.0,
+        torpedo_bonus: 1.0,
+        jamming: 1.0,
+        plan: plan.clone(),
+        is_starbase: false,
+        is_bomber: false,
+        is_fuel_transport: false,
+        is_freighter: false,
+        challenged: false
+    }
+}
+
+/*
+    Runs the battle on the 10x10 board.  Each side starts together in its
+    own square.  Every round the tokens first move as their battle plan's
+    tactic directs, then every weapon fires once in order of initiative,
+    with ties settled at random.  Tokens fight anyone their plan attacks
+    and anyone whose plan attacks them.  The battle ends after 16 rounds or
+    once no armed token has an enemy left.
+*/
+fn fight(universe: &Universe, rng: &mut StdRng, battle: &mut Battle) {
+    let mut sides : Vec<u8> = battle.tokens.iter().map(|t| t.owner_id).collect();
+    sides.sort();
+    sides.dedup();
+
+    for (i, t) in battle.tokens.iter_mut().enumerate() {
+        let side = sides.iter().position(|s| *s == t.owner_id).unwrap();
+        let square = STARTING_SQUARES[side % STARTING_SQUARES.len()];
+        t.x = square.0;
+        t.y = square.1;
+        battle.events.push(BattleEvent::Place { token: i as u16, x: t.x as i8, y: t.y as i8 });
+    }
+
+    let hostile : Vec<Vec<bool>> = battle.tokens.iter()
+        .map(|a| battle.tokens.iter().map(|b| wants_to_attack(universe, a, b) || wants_to_attack(universe, b, a)).collect())
+        .collect();
+
+    for round in 0..MAX_BATTLE_ROUNDS {
+        let fighting = (0..battle.tokens.len()).any(|i| {
+            battle.tokens[i].is_alive() && battle.tokens[i].is_armed()
+                && (0..battle.tokens.len()).any(|j| hostile[i][j] && battle.tokens[j].is_targetable())
+        });
+        if !fighting {
+            break;
+        }
+
+        battle.rounds = round + 1;
+        battle.events.push(BattleEvent::Round(battle.rounds));
+        move_tokens(&mut battle.tokens, &hostile, &mut battle.events);
+        fire_weapons(rng, &mut battle.tokens, &hostile, &mut battle.events);
+    }
+}
+
+fn nearest_enemy(tokens: &[BattleToken], hostile: &[Vec<bool>], index: usize) -> Option<usize> {
+    (0..tokens.len())
+        .filter(|j| hostile[index][*j] && tokens[*j].is_targetable())
+        .min_by_key(|j| (tokens[index].distance_to(&tokens[*j]), *j))
+}
+
+// 0 for the plan's primary target class, 1 for its secondary
+fn target_rank(tokens: &[BattleToken], index: usize, target: usize) -> Option<u8> {
+    let plan = &tokens[index].plan;
+
+    if tokens[target].matches(plan.primary_target) {
+        Some(0)
+    }
+    else if tokens[target].matches(plan.secondary_target) {
+        Some(1)
+    }
+    else {
+        None
+    }
+}
+
+fn preferred_target(tokens: &[BattleToken], hostile: &[Vec<bool>], index: usize) -> Option<usize> {
+    (0..tokens.len())
+        .filter(|j| hostile[index][*j] && tokens[*j].is_targetable())
+        .filter_map(|j| target_rank(tokens, index, j).map(|r| (r, tokens[index].distance_to(&tokens[j]), j)))
+        .min()
+        .map(|(_, _, j)| j)
+}
+
+/*
+    Armed tokens head for their preferred target until it is at the range
+    their tactic calls for; those minimizing damage also back away from
+    anything closer.  Unarmed and disengaging tokens run from the nearest
+    enemy.
+*/
+fn move_tokens(tokens: &mut [BattleToken], hostile: &[Vec<bool>], events: &mut Vec<BattleEvent>) {
+    for i in 0..tokens.len() {
+        if !tokens[i].is_alive() || tokens[i].movement == 0.0 {
+            continue;
+        }
+
+        tokens[i].movement_left += tokens[i].movement;
+        while tokens[i].movement_left >= 1.0 {
+            tokens[i].movement_left -= 1.0;
+
+            let nearest = match nearest_enemy(tokens, hostile, i) {
+                Some(j) => j,
+                None => break
+            };
+
+            let (x, y) = (tokens[i].x, tokens[i].y);
+            let away = ((x - tokens[nearest].x).signum(), (y - tokens[nearest].y).signum());
+
+            let (dx, dy) = match tokens[i].desired_range() {
+                None => away,
+                Some(range) => {
+                    let target = preferred_target(tokens, hostile, i).unwrap_or(nearest);
+                    let distance = tokens[i].distance_to(&tokens[target]);
+
+                    if distance > range {
+                        ((tokens[target].x - x).signum(), (tokens[target].y - y).signum())
+                    }
+                    else if tokens[i].plan.tactic == BattleTactic::MinimizeDamage && tokens[i].distance_to(&tokens[nearest]) < range {
+                        away
+                    }
+                    else {
+                        break;
+                    }
+                }
+            };
+
+            tokens[i].x = (x + dx).clamp(0, BATTLE_BOARD_SIZE - 1);
+            tokens[i].y = (y + dy).clamp(0, BATTLE_BOARD_SIZE - 1);
+
+            if (tokens[i].x, tokens[i].y) != (x, y) {
+                events.push(BattleEvent::Move { token: i as u16, x: tokens[i].x as i8, y: tokens[i].y as i8 });
+            }
+        }
+    }
+}
+
+fn fire_weapons(rng: &mut StdRng, tokens: &mut [BattleToken], hostile: &[Vec<bool>], events: &mut Vec<BattleEvent>) {
+    let mut shots : Vec<(u32, u32, usize, usize)> = Vec::new();
+    for (i, t) in tokens.iter().enumerate() {
+        for (w, weapon) in t.weapons.iter().enumerate() {
+            shots.push((weapon.initiative, rng.gen::<u32>(), i, w));
+        }
+    }
+    shots.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    for (_, _, i, w) in shots {
+        if !tokens[i].is_alive() {
+            continue;
+        }
+
+        let weapon = tokens[i].weapons[w].clone();
+        match weapon.accuracy {
+            None => fire_beam(tokens, hostile, events, i, &weapon),
+            Some(accuracy) => fire_torpedoes(rng, tokens, hostile, events, i, &weapon, accuracy)
+        }
+    }
+}
+
+/*
+    Targets of the plan's primary class come first, then the secondary
+    class, nearest first within each.  Tokens matching neither are not
+    fired on.
+*/
+fn targets_in_range(tokens: &[BattleToken], hostile: &[Vec<bool>], index: usize, range: i32) -> Vec<usize> {
+    let mut targets : Vec<(u8, i32, usize)> = (0..tokens.len())
+        .filter(|j| hostile[index][*j] && tokens[*j].is_targetable() && tokens[index].distance_to(&tokens[*j]) <= range)
+        .filter_map(|j| target_rank(tokens, index, j).map(|r| (r, tokens[index].distance_to(&tokens[j]), j)))
+        .collect();
+    targets.sort();
+    targets.into_iter().map(|(_, _, j)| j).collect()
+}
+
+/*
+    Beams hit for their full power at point blank range, dropping off to
+    90% at their maximum range.  Capacitors add to and deflectors take away
+    from the damage.  A beam that destroys its target carries on into the
+    next one in range; gatlings hit everything in range at once; sappers
+    only drain shields.
+*/
+fn fire_beam(tokens: &mut [BattleToken], hostile: &[Vec<bool>], events: &mut Vec<BattleEvent>, index: usize, weapon: &BattleWeapon) {
+    let targets = targets_in_range(tokens, hostile, index, weapon.range);
+    let base = (weapon.power * weapon.count * tokens[index].quantity as u32) as f64 * tokens[index].beam_bonus;
+    let mut remaining = base;
+
+    for j in targets {
+        let distance = tokens[index].distance_to(&tokens[j]) as f64;
+        let dropoff = if weapon.range == 0 { 1.0 } else { 1.0 - BEAM_DROPOFF_PERCENT / 100.0 * distance / weapon.range as f64 };
+        let reduction = tokens[j].beam_reduction;
+
+        let shot = if weapon.is_spread { base } else { remaining };
+        let damage = (shot * dropoff * reduction) as u32;
+        let hit = if weapon.hits_shields_only {
+            let drained = damage.min(tokens[j].shields);
+            tokens[j].shields -= drained;
+            (drained, 0, 0)
+        }
+        else {
+            tokens[j].take_damage(damage, 0)
+        };
+        record_hit(events, index, j, weapon, hit);
+
+        let absorbed = hit.0 + hit.1;
+        if !weapon.is_spread {
+            remaining -= absorbed as f64 / dropoff / reduction;
+            if remaining < 1.0 || weapon.hits_shields_only {
+                break;
+            }
+        }
+    }
+}
+
+/*
+    Each torpedo rolls to hit against its accuracy, improved by the firing
+    ship's computers and reduced by the target's jammers.  A hit does half
+    its damage to shields and half to armor; a miss only scratches the
+    shields.  Capital missiles double their damage against unshielded
+    targets.
+*/
+fn fire_torpedoes(rng: &mut StdRng, tokens: &mut [BattleToken], hostile: &[Vec<bool>], events: &mut Vec<BattleEvent>, index: usize, weapon: &BattleWeapon, accuracy: u32) {
+    let shots = weapon.count * tokens[index].quantity as u32;
+
+    // Hits are totalled per target so a volley records one shot per target
+    let mut volley : Vec<(usize, (u32, u32, u16))> = Vec::new();
+
+    for _ in 0..shots {
+        let j = match targets_in_range(tokens, hostile, index, weapon.range).first() {
+            Some(j) => *j,
+            None => break
+        };
+
+        let inaccuracy = (100 - accuracy.min(100)) as f64 * tokens[index].torpedo_bonus;
+        let chance = (100.0 - inaccuracy) * tokens[j].jamming;
+
+        let hit = if rng.gen::<f64>() * 100.0 < chance {
+            let mut damage = weapon.power;
+            if weapon.is_capital_missile && tokens[j].shields == 0 {
+                damage *= CAPITAL_MISSILE_FACTOR;
+            }
+            tokens[j].take_damage(damage / 2, damage - damage / 2)
+        }
+        else {
+            let scratch = (weapon.power as f64 * TORPEDO_MISS_SHIELD_FACTOR) as u32;
+            let drained = scratch.min(tokens[j].shields);
+            tokens[j].shields -= drained;
+            (drained, 0, 0)
+        };
+
+        match volley.iter_mut().find(|v| v.0 == j) {
+            Some(v) => {
+                (v.1).0 += hit.0;
+                (v.1).1 += hit.1;
+                (v.1).2 += hit.2;
+            },
+            None => volley.push((j, hit))
+        }
+    }
+
+    for (j, hit) in volley {
+        record_hit(events, index, j, weapon, hit);
+    }
+}
+
+fn apply_battle_results(universe: &mut Universe, battle: &Battle) {
+    let mut fleet_ids : Vec<u32> = battle.tokens.iter()
+        .filter_map(|t| match t.source {
+            TokenSource::Fleet(fid) => Some(fid),
+            TokenSource::Starbase(_) | TokenSource::Planet(_) => None
+        })
+        .collect();
+    fleet_ids.dedup();
+
+    for fid in fleet_ids {
+        let mut tokens : Vec<&BattleToken> = battle.tokens.iter()
+            .filter(|t| t.source == TokenSource::Fleet(fid))
+            .collect();
+
+        // Stacks that did not fight (their design could not be found) are
+        // carried through unchanged
+        let mut members : Vec<FleetMember> = Vec::new();
+        for m in universe.fleets.get(&fid).map_or(&[][..], |f| &f.members[..]) {
+            match tokens.iter().position(|t| t.design_id == m.design_id) {
+                Some(i) => {
+                    let t = tokens.remove(i);
+                    if t.is_alive() {
+                        members.push(FleetMember { design_id: t.design_id, quantity: t.quantity, damage: t.damage });
+                    }
+                },
+                None => members.push(m.clone())
+            }
+        }
+
+        if members.is_empty() {
+            universe.remove_fleet(fid);
+        }
+        else if let Some(f) = universe.fleets.get_mut(&fid) {
+            f.members = members;
+            universe.recalculate_fleet_capacities(fid);
+        }
+    }
+
+    for t in battle.tokens.iter() {
+        if let TokenSource::Starbase(sid) = t.source {
+            if t.is_alive() {
+                if let Some(sb) = universe.starbases.get_mut(&sid) {
+                    sb.damage = t.damage;
+                }
+            }
+            else {
+                universe.remove_starbase(sid);
+            }
+        }
+    }
+}
+
+/*
+    Each side is told how many of its own forces and of the enemy's took
+    part and were lost.  A planet's defenses count as one force on its
+    owner's side; they cannot be destroyed, so a planet is never lost.
+*/
+fn send_battle_messages(universe: &mut Universe, battle: &Battle) {
+    let mut sides : Vec<u8> = battle.tokens.iter().map(|t| t.owner_id).collect();
+    sides.sort();
+    sides.dedup();
+
+    let where_text = universe.describe_location(&battle.location);
+
+    for pid in sides.iter() {
+        let count = |ours: bool, lost: bool| -> u32 {
+            battle.tokens.iter()
+                .filter(|t| (t.owner_id == *pid) == ours)
+                .map(|t| if lost { (t.starting_quantity - t.quantity) as u32 } else { t.starting_quantity as u32 })
+                .sum()
+        };
+
+        let (our_count, our_lost) = (count(true, false), count(true, true));
+        let (enemy_count, enemy_lost) = (count(false, false), count(false, true));
+
+        let message = if sides.len() == 2 {
+            let other = sides.iter().find(|s| *s != pid).unwrap();
+            let race = universe.players[*other as usize].race.plural_name.clone();
+
+            if our_lost == our_count && enemy_lost == 0 {
+                (MessageId::BattleLostAll, vec![where_text.clone(), race, enemy_count.to_string()])
+            }
+            else if our_lost == our_count {
+                (MessageId::BattleLostAllDestroyedSome, vec![where_text.clone(), race, enemy_count.to_string(), enemy_lost.to_string()])
+            }
+            else if enemy_lost == enemy_count && our_lost == 0 {
+                (MessageId::BattleWonNoLosses, vec![where_text.clone(), race, our_count.to_string()])
+            }
+            else if enemy_lost == enemy_count {
+                (MessageId::BattleWonWithLosses, vec![where_text.clone(), race, our_count.to_string(), our_lost.to_string()])
+            }
+            else {
+                (MessageId::BattleUndecided, vec![where_text.clone(), race, our_count.to_string(), enemy_count.to_string(), our_lost.to_string(), enemy_lost.to_string()])
+            }
+        }
+        else {
+            let races = sides.len().to_string();
+
+            if our_lost == our_count && enemy_lost == 0 {
+                (MessageId::MultiBattleLostAll, vec![where_text.clone(), races, our_count.to_string(), enemy_count.to_string()])
+            }
+            else if our_lost == our_count {
+                (MessageId::MultiBattleLostAllDestroyedSome, vec![where_text.clone(), races, our_count.to_string(), enemy_count.to_string(), enemy_lost.to_string()])
+            }
+            else if enemy_lost == enemy_count && our_lost == 0 {
+                (MessageId::MultiBattleWonNoLosses, vec![where_text.clone(), races, our_count.to_string()])
+            }
+            else if enemy_lost == enemy_count {
+                (MessageId::MultiBattleWonWithLosses, vec![where_text.clone(), races, our_lost.to_string(), our_count.to_string()])
+            }
+            else {
+                (MessageId::MultiBattleUndecided, vec![where_text.clone(), races, our_lost.to_string(), our_count.to_string(), enemy_lost.to_string(), enemy_count.to_string()])
+            }
+        };
+
+        universe.players[*pid as usize].add_message(message.0, message.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::game::objects::player::Player;
+    use ::game::objects::message::Message;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::objects::battle::construct_default_battle_plans;
+    use ::game::tutorial::generate_tutorial_universe;
+    use rand::SeedableRng;
+
+    // The tutorial map with two Humanoid players at war
+    fn two_player_universe() -> Universe {
+        let mut u = generate_tutorial_universe();
+        for pid in 0..2 {
+            let mut player = Player::construct_from_race(create_humanoid());
+            player.id = pid;
+            u.players.push(player);
+        }
+        u
+    }
+
+    fn token(source: TokenSource, owner_id: u8, quantity: u16, armor: u32) -> BattleToken {
+        BattleToken {
+            source,
+            owner_id,
+            design_id: 0,
+            design_name: "Test".to_string(),
+            quantity,
+            starting_quantity: quantity,
+            armor,
+            damage: 0,
+            shields_per_ship: 0,
+            shields: 0,
+            x: 0,
+            y: 0,
+            movement: 0.0,
+            movement_left: 0.0,
+            weapons: Vec::new(),
+            beam_reduction: 1.0,
+            beam_bonus: 1.0,
+            torpedo_bonus: 1.0,
+            jamming: 1.0,
+            plan: construct_default_battle_plans().remove(0),
+            is_starbase: false,
+            is_bomber: false,
+            is_fuel_transport: false,
+            is_freighter: false,
+            challenged: false
+        }
+    }
+
+    fn weapon(power: u32, range: i32, accuracy: Option<u32>) -> BattleWeapon {
+        BattleWeapon {
+            tid: TechnologyId::Laser,
+            count: 1,
+            power,
+            range,
+            initiative: 0,
+            accuracy,
+            is_spread: false,
+            hits_shields_only: false,
+            is_capital_missile: false
+        }
+    }
+
+    // Token 0 against every other token
+    fn first_against_rest(count: usize) -> Vec<Vec<bool>> {
+        (0..count).map(|i| (0..count).map(|j| (i == 0) != (j == 0)).collect()).collect()
+    }
+
+    fn message_ids(messages: &[Message]) -> Vec<u16> {
+        messages.iter().map(|m| m.message_id).collect()
+    }
+
+    #[test]
+    fn planet_defenses_count_as_a_force_in_battle_messages() {
+        let mut u = two_player_universe();
+        let location = u.planets[0].location.clone();
+
+        let planet = token(TokenSource::Planet(1), 0, 1, 0);
+        let mut attacker = token(TokenSource::Fleet(1), 1, 3, 20);
+        attacker.quantity = 0;
+
+        let battle = Battle { location, tokens: vec![planet, attacker], rounds: 1, events: Vec::new() };
+        send_battle_messages(&mut u, &battle);
+
+        assert_eq!(message_ids(&u.players[0].messages), vec![MessageId::BattleWonNoLosses as u16]);
+        assert_eq!(u.players[0].messages[0].parameters[2], "1");
+        assert_eq!(message_ids(&u.players[1].messages), vec![MessageId::BattleLostAll as u16]);
+        assert_eq!(u.players[1].messages[0].parameters[2], "1");
+    }
+
+    #[test]
+    fn damage_goes_to_shields_first_and_destroys_whole_ships() {
+        let mut t = token(TokenSource::Fleet(1), 0, 3, 10);
+        t.shields_per_ship = 5;
+        t.shields = 15;
+
+        assert_eq!(t.take_damage(20, 0), (15, 5, 0));
+        assert_eq!(t.take_damage(0, 20), (0, 20, 2));
+        assert_eq!((t.quantity, t.damage, t.shields), (1, 5, 0));
+        assert!(t.challenged);
+    }
+
+    #[test]
+    fn damage_beyond_the_remaining_armor_is_not_absorbed() {
+        let mut t = token(TokenSource::Fleet(1), 0, 2, 10);
+
+        assert_eq!(t.take_damage(0, 100), (0, 20, 2));
+        assert!(!t.is_alive());
+        assert_eq!(t.take_damage(0, 100), (0, 0, 0));
+    }
+
+    #[test]
+    fn beams_drop_off_to_ninety_percent_at_maximum_range() {
+        let mut tokens = vec![token(TokenSource::Fleet(1), 0, 1, 10), token(TokenSource::Fleet(2), 1, 1, 1000)];
+        tokens[0].weapons.push(weapon(100, 2, None));
+        tokens[1].x = 2;
+
+        let w = tokens[0].weapons[0].clone();
+        fire_beam(&mut tokens, &first_against_rest(2), &mut Vec::new(), 0, &w);
+        assert_eq!(tokens[1].damage, 90);
+    }
+
+    #[test]
+    fn beams_carry_over_into_the_next_target() {
+        let mut tokens = vec![
+            token(TokenSource::Fleet(1), 0, 1, 10),
+            token(TokenSource::Fleet(2), 1, 1, 30),
+            token(TokenSource::Fleet(3), 1, 1, 1000)
+        ];
+        tokens[0].weapons.push(weapon(100, 0, None));
+
+        let w = tokens[0].weapons[0].clone();
+        fire_beam(&mut tokens, &first_against_rest(3), &mut Vec::new(), 0, &w);
+        assert!(!tokens[1].is_alive());
+        assert_eq!(tokens[2].damage, 70);
+    }
+
+    #[test]
+    fn torpedo_hits_split_damage_and_misses_only_scratch_shields() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for &(accuracy, shields, damage) in [(100, 75, 25), (0, 94, 0)].iter() {
+            let mut tokens = vec![token(TokenSource::Fleet(1), 0, 1, 10), token(TokenSource::Fleet(2), 1, 1, 1000)];
+            tokens[0].weapons.push(weapon(50, 4, Some(accuracy)));
+            tokens[1].shields_per_ship = 100;
+            tokens[1].shields = 100;
+
+            let w = tokens[0].weapons[0].clone();
+            fire_torpedoes(&mut rng, &mut tokens, &first_against_rest(2), &mut Vec::new(), 0, &w, accuracy);
+            assert_eq!((tokens[1].shields, tokens[1].damage), (shields, damage));
+        }
+    }
+
+    #[test]
+    fn tokens_move_as_their_tactic_calls_for() {
+        let plans = construct_default_battle_plans();
+        let cases = [
+            // Default closes to its longest range
+            (plans[0].clone(), true, 5),
+            // Chicken runs
+            (plans[4].clone(), true, 3),
+            // Unarmed ships always run
+            (plans[0].clone(), false, 3)
+        ];
+
+        for &(ref plan, armed, x) in cases.iter() {
+            let mut tokens = vec![token(TokenSource::Fleet(1), 0, 1, 10), token(TokenSource::Fleet(2), 1, 1, 10)];
+            tokens[0].plan = plan.clone();
+            tokens[0].movement = 1.0;
+            tokens[0].x = 4;
+            tokens[1].x = 8;
+            if armed {
+                tokens[0].weapons.push(weapon(10, 2, None));
+            }
+
+            move_tokens(&mut tokens, &first_against_rest(2), &mut Vec::new());
+            assert_eq!((tokens[0].x, tokens[1].x), (x, 8));
+        }
+    }
+
+    #[test]
+    fn minimizing_damage_backs_away_from_enemies_inside_weapon_range() {
+        let mut tokens = vec![token(TokenSource::Fleet(1), 0, 1, 10), token(TokenSource::Fleet(2), 1, 1, 10)];
+        tokens[0].plan = construct_default_battle_plans().remove(3);
+        tokens[0].movement = 1.0;
+        tokens[0].weapons.push(weapon(10, 3, None));
+        tokens[0].x = 4;
+        tokens[1].x = 5;
+
+        move_tokens(&mut tokens, &first_against_rest(2), &mut Vec::new());
+        assert_eq!(tokens[0].x, 3);
+    }
+
+    #[test]
+    fn battle_results_keep_fleet_members_that_did_not_fight() {
+        let mut u = two_player_universe();
+        let location = u.planets[0].location.clone();
+
+        let mut design = ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Scout".to_string(),
+            base_hull: TechnologyId::Scout,
+            is_obsolete: false,
+            slots: Some(Default::default())
+        };
+        u.players[1].add_ship_design(design.clone());
+        u.players[1].add_ship_design(design.clone());
+        let fought = u.players[1].ship_designs[0].as_ref().unwrap().id;
+        let stayed = u.players[1].ship_designs[1].as_ref().unwrap().id;
+
+        design.id = fought;
+        let fid = u.add_fleet(&design, Some(1), location.clone(), 4);
+        u.fleets.get_mut(&fid).unwrap().members.push(FleetMember { design_id: stayed, quantity: 2, damage: 0 });
+
+        let mut survivors = token(TokenSource::Fleet(fid), 1, 4, 20);
+        survivors.design_id = fought;
+        survivors.quantity = 1;
+        survivors.damage = 5;
+
+        let battle = Battle { location: location.clone(), tokens: vec![survivors.clone()], rounds: 1, events: Vec::new() };
+        apply_battle_results(&mut u, &battle);
+
+        let members : Vec<(u32, u16, u32)> = u.fleets[&fid].members.iter().map(|m| (m.design_id, m.quantity, m.damage)).collect();
+        assert_eq!(members, vec![(fought, 1, 5), (stayed, 2, 0)]);
+
+        survivors.quantity = 0;
+        let mut destroyed = survivors.clone();
+        destroyed.design_id = stayed;
+        let battle = Battle { location, tokens: vec![survivors, destroyed], rounds: 1, events: Vec::new() };
+        apply_battle_results(&mut u, &battle);
+        assert!(!u.fleets.contains_key(&fid));
+    }
+}
@@ -0,0 +1,226 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::tech::TechnologyId;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::player::PlayerRelation;
+use ::game::objects::message::MessageId;
+
+// Smart bombs have their listed effect on a planet ideal for its owner
+// and up to twice that on one the owner can not live on
+pub const SMART_BOMB_MAX_HABITABILITY_FACTOR : f64 = 2.0;
+
+// Clicks of terraforming each retro bomb undoes
+pub const RETRO_BOMB_CLICKS : u32 = 1;
+
+/*
+    Bomb totals for one fleet.  Percentages are summed over every bomb.
+*/
+#[derive(Default)]
+struct BombLoad {
+    bombs: u32,
+    kill_percent: f64,
+    min_killed: u32,
+    buildings: u32,
+    smart_bombs: u32,
+    smart_kill_percent: f64,
+    retro_bombs: u32
+}
+
+fn calculate_bomb_load(universe: &Universe, fleet_id: u32) -> BombLoad {
+    let mut load = BombLoad::default();
+
+    for m in universe.fleets[&fleet_id].members.iter() {
+        let design = match universe.lookup_ship_design(m.design_id) {
+            Some(d) => d,
+            None => continue
+        };
+
+        for s in design.filled_slots() {
+            let tech = &TECHNOLOGY_DETAILS[s.tid as usize];
+            let count = s.amount as u32 * m.quantity as u32;
+
+            if s.tid == TechnologyId::RetroBomb {
+                load.retro_bombs += count;
+            }
+            else if tech.is_smart == Some(true) {
+                load.smart_bombs += count;
+                load.smart_kill_percent += tech.colonist_kill_percent.unwrap_or(0.0) as f64 * count as f64;
+            }
+            else if tech.colonist_kill_percent.is_some() {
+                load.bombs += count;
+                load.kill_percent += tech.colonist_kill_percent.unwrap_or(0.0) as f64 * count as f64;
+                load.min_killed += tech.min_colonists_killed.unwrap_or(0) as u32 * count;
+                load.buildings += tech.buildings_destroyed.unwrap_or(0) as u32 * count;
+            }
+        }
+    }
+
+    load
+}
+
+/*
+    Fleets in orbit of an enemy planet bomb it once nothing is left to
+    defend it from orbit, that is once the planet has no starbase.  Fleets
+    bomb one after another in id order.
+*/
+pub fn bomb_planets(universe: &mut Universe) {
+    let planet_ids : Vec<u32> = universe.planets.iter()
+        .filter(|p| p.owner_id.is_some() && p.related_starbase.is_none())
+        .map(|p| p.id)
+        .collect();
+
+    for pid in planet_ids {
+        let (owner_id, location) = {
+            let p = universe.get_planet(pid).unwrap();
+            (p.owner_id.unwrap(), p.location.clone())
+        };
+
+        let mut fleet_ids : Vec<u32> = universe.fleets.iter()
+            .filter(|&(_, f)| match f.owner_id {
+                Some(o) => f.location.is_same_location(&location)
+                    && o != owner_id
+                    && universe.players[o as usize].relation_to(owner_id) == PlayerRelation::Enemy,
+                None => false
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        fleet_ids.sort();
+
+        for fid in fleet_ids {
+            if universe.get_planet(pid).unwrap().owner_id.is_none() {
+                break;
+            }
+            bomb_planet(universe, fid, pid);
+        }
+    }
+}
+
+/*
+    Normal bombs kill a share of the colonists, but never fewer than the
+    bombs' minimum, and knock down installations.  Smart bombs kill a share
+    of the colonists that grows the less habitable the planet is to its
+    owner.  Defense coverage stops that share of both.  Retro bombs undo
+    terraforming and are not stopped by defenses.
+*/
+fn bomb_planet(universe: &mut Universe, fleet_id: u32, planet_id: u32) {
+    let load = calculate_bomb_load(universe, fleet_id);
+    if load.bombs + load.smart_bombs + load.retro_bombs == 0 {
+        return;
+    }
+
+    let (fleet_name, attacker_id) = {
+        let f = &universe.fleets[&fleet_id];
+        (f.name.clone(), f.owner_id.unwrap())
+    };
+    let attacker_name = format!("{} {}", universe.players[attacker_id as usize].race.name, fleet_name);
+
+    let defender_id = universe.get_planet(planet_id).unwrap().owner_id.unwrap();
+    let habitability = {
+        let p = universe.get_planet(planet_id).unwrap();
+        universe.players[defender_id as usize].race.planet_habitability(&p.habitat)
+    };
+
+    let p = universe.get_planet_mut(planet_id).unwrap();
+    let coverage = p.defense_coverage();
+    let smart_coverage = p.smart_bomb_defense_coverage();
+    let mut killed = 0;
+    let mut destroyed = 0;
+
+    if load.bombs > 0 {
+        let by_percent = p.population as f64 * load.kill_percent / 100.0;
+        let kill = by_percent.max(load.min_killed as f64) * (1.0 - coverage);
+        killed += (kill as u32).min(p.population);
+        p.population -= killed;
+
+        destroyed = p.destroy_installations((load.buildings as f64 * (1.0 - coverage)) as u32);
+    }
+
+    if load.smart_bombs > 0 {
+        let factor = SMART_BOMB_MAX_HABITABILITY_FACTOR - habitability;
+        let kill = p.population as f64 * load.smart_kill_percent / 100.0 * factor * (1.0 - smart_coverage);
+        let smart_killed = (kill as u32).min(p.population);
+        killed += smart_killed;
+        p.population -= smart_killed;
+    }
+
+    let stopped = (load.bombs as f64 * coverage + load.smart_bombs as f64 * smart_coverage).round() as u32;
+    let undone = if load.retro_bombs > 0 { p.undo_terraforming(load.retro_bombs * RETRO_BOMB_CLICKS) } else { 0 };
+
+    let planet_name = p.name.clone();
+    let wiped_out = p.population == 0;
+    if wiped_out {
+        p.owner_id = None;
+    }
+
+    let (killed_text, destroyed_text, stopped_text) = (killed.to_string(), destroyed.to_string(), stopped.to_string());
+    let messages = if wiped_out {
+        Some(((MessageId::BombingKilledAllEnemy, vec![fleet_name.clone(), planet_name.clone()]),
+              (MessageId::BombedKilledAll, vec![attacker_name.clone(), planet_name.clone()])))
+    }
+    else {
+        let ids = match (killed > 0, destroyed, stopped > 0) {
+            (false, 0, _) => None,
+            (true, 0, false) => Some((MessageId::BombingKilled, MessageId::BombedKilled)),
+            (true, 0, true) => Some((MessageId::BombingKilledStopped, MessageId::BombedKilledStopped)),
+            (false, 1, false) => Some((MessageId::BombingDestroyedOne, MessageId::BombedDestroyedOne)),
+            (false, 1, true) => Some((MessageId::BombingDestroyedOneStopped, MessageId::BombedDestroyedOneStopped)),
+            (false, _, false) => Some((MessageId::BombingDestroyed, MessageId::BombedDestroyed)),
+            (false, _, true) => Some((MessageId::BombingDestroyedStopped, MessageId::BombedDestroyedStopped)),
+            (true, 1, false) => Some((MessageId::BombingKilledDestroyedOne, MessageId::BombedKilledDestroyedOne)),
+            (true, 1, true) => Some((MessageId::BombingKilledDestroyedOneStopped, MessageId::BombedKilledDestroyedOneStopped)),
+            (true, _, false) => Some((MessageId::BombingKilledDestroyed, MessageId::BombedKilledDestroyed)),
+            (true, _, true) => Some((MessageId::BombingKilledDestroyedStopped, MessageId::BombedKilledDestroyedStopped))
+        };
+
+        ids.map(|(attack, defend)| {
+            let mut parameters = Vec::new();
+            if killed > 0 {
+                parameters.push(killed_text.clone());
+            }
+            if destroyed > 1 {
+                parameters.push(destroyed_text.clone());
+            }
+            if stopped > 0 {
+                parameters.push(stopped_text.clone());
+            }
+
+            let mut attacker_parameters = vec![fleet_name.clone(), planet_name.clone()];
+            attacker_parameters.extend(parameters.iter().cloned());
+            let mut defender_parameters = vec![attacker_name.clone(), planet_name.clone()];
+            defender_parameters.extend(parameters);
+
+            ((attack, attacker_parameters), (defend, defender_parameters))
+        })
+    };
+
+    if let Some((attack, defend)) = messages {
+        universe.players[attacker_id as usize].add_message(attack.0, attack.1);
+        universe.players[defender_id as usize].add_message(defend.0, defend.1);
+    }
+
+    if undone > 0 {
+        let parameters = vec![fleet_name, planet_name, undone.to_string()];
+        universe.players[attacker_id as usize].add_message(MessageId::RetroBombed, parameters.clone());
+        universe.players[defender_id as usize].add_message(MessageId::RetroBombed, parameters);
+    }
+}
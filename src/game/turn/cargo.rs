@@ -0,0 +1,266 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::CargoType;
+use ::game::objects::fleet::CargoTransfer;
+use ::game::objects::fleet::TransferAmount;
+use ::game::objects::fleet::WaypointTarget;
+use ::game::objects::fleet::COLONISTS_PER_KT;
+use ::game::objects::fleet::Fleet;
+use ::game::objects::planet::Planet;
+use ::game::objects::player::PlayerRelation;
+use ::game::objects::message::MessageId;
+use ::game::turn::invasion::GroundAssault;
+
+pub fn cargo_type_name(cargo_type: CargoType) -> &'static str {
+    match cargo_type {
+        CargoType::Ironium => "Ironium",
+        CargoType::Boranium => "Boranium",
+        CargoType::Germanium => "Germanium",
+        CargoType::Colonists => "colonists"
+    }
+}
+
+/*
+    Amount (in kT) of a cargo type a planet can give up.  A planet always
+    keeps its last kT of colonists so that loading never abandons a world.
+*/
+fn planet_available(planet: &Planet, cargo_type: CargoType) -> u32 {
+    match cargo_type {
+        CargoType::Ironium => planet.on_surface.ironium as u32,
+        CargoType::Boranium => planet.on_surface.boranium as u32,
+        CargoType::Germanium => planet.on_surface.germanium as u32,
+        CargoType::Colonists => (planet.population / COLONISTS_PER_KT).saturating_sub(1)
+    }
+}
+
+fn planet_space(planet: &Planet, cargo_type: CargoType) -> u32 {
+    match cargo_type {
+        CargoType::Ironium => (u16::MAX - planet.on_surface.ironium) as u32,
+        CargoType::Boranium => (u16::MAX - planet.on_surface.boranium) as u32,
+        CargoType::Germanium => (u16::MAX - planet.on_surface.germanium) as u32,
+        CargoType::Colonists => u32::MAX
+    }
+}
+
+fn planet_add(planet: &mut Planet, cargo_type: CargoType, amount: u32) {
+    match cargo_type {
+        CargoType::Ironium => planet.on_surface.ironium += amount as u16,
+        CargoType::Boranium => planet.on_surface.boranium += amount as u16,
+        CargoType::Germanium => planet.on_surface.germanium += amount as u16,
+        CargoType::Colonists => planet.population += amount * COLONISTS_PER_KT
+    }
+}
+
+fn planet_remove(planet: &mut Planet, cargo_type: CargoType, amount: u32) {
+    match cargo_type {
+        CargoType::Ironium => planet.on_surface.ironium -= amount as u16,
+        CargoType::Boranium => planet.on_surface.boranium -= amount as u16,
+        CargoType::Germanium => planet.on_surface.germanium -= amount as u16,
+        CargoType::Colonists => planet.population -= amount * COLONISTS_PER_KT
+    }
+}
+
+fn transfer_message_parameters(fleet_name: &str, cargo_type: CargoType, amount: u32, planet_name: &str) -> (bool, Vec<String>) {
+    match cargo_type {
+        CargoType::Colonists => (true, vec![fleet_name.to_string(), (amount * COLONISTS_PER_KT).to_string(), cargo_type_name(cargo_type).to_string(), planet_name.to_string()]),
+        _ => (false, vec![fleet_name.to_string(), format!("{}kT", amount), cargo_type_name(cargo_type).to_string(), planet_name.to_string()])
+    }
+}
+
+fn waypoint_planet_index(universe: &Universe, fleet_id: u32) -> Option<usize> {
+    let f = &universe.fleets[&fleet_id];
+
+    match f.orders[0].target {
+        WaypointTarget::Planet(pid) => universe.planets.iter()
+            .position(|p| p.id == pid && p.location.is_same_location(&f.location)),
+        _ => None
+    }
+}
+
+// Owner and target of a fleet whose waypoint is another fleet
+fn waypoint_fleet(universe: &Universe, fleet_id: u32) -> Option<(u8, u32)> {
+    let f = &universe.fleets[&fleet_id];
+
+    match f.orders[0].target {
+        WaypointTarget::Fleet(tid) if universe.fleets.contains_key(&tid) => Some((f.owner_id?, tid)),
+        _ => None
+    }
+}
+
+/*
+    Loads cargo from the planet at the fleet's waypoint.  Amounts are capped
+    by what the planet has and by the fleet's free cargo space.  "Fill to %"
+    loads until that cargo type takes up the given share of the hold.
+    Cargo can only be loaded from the fleet owner's own planets.  When the
+    waypoint is one of the owner's fleets, cargo is taken from that fleet.
+*/
+pub fn load_cargo(universe: &mut Universe, fleet_id: u32, transfers: &[CargoTransfer]) {
+    if let Some((owner_id, target_fleet_id)) = waypoint_fleet(universe, fleet_id) {
+        if universe.fleets[&target_fleet_id].owner_id == Some(owner_id) {
+            universe.transfer_cargo(owner_id, target_fleet_id, fleet_id, transfers);
+        }
+        return;
+    }
+
+    let planet_index = match waypoint_planet_index(universe, fleet_id) {
+        Some(i) => i,
+        None => return
+    };
+
+    let (owner_id, name) = {
+        let f = &universe.fleets[&fleet_id];
+        (f.owner_id, f.name.clone())
+    };
+
+    let mut messages = Vec::new();
+
+    for t in transfers.iter() {
+        let planet = &mut universe.planets[planet_index];
+
+        if planet.owner_id != owner_id || owner_id.is_none() {
+            messages.push((MessageId::LoadFromForeignPlanet, vec![name.clone(), cargo_type_name(t.cargo_type).to_string()]));
+            continue;
+        }
+
+        let f = universe.fleets.get_mut(&fleet_id).unwrap();
+        let carried = f.cargo.get(t.cargo_type);
+        let requested = match t.amount {
+            TransferAmount::Amount(a) => a,
+            TransferAmount::All => u32::MAX,
+            TransferAmount::FillPercent(percent) => {
+                let target = f.total_cargo_capacity * (percent.min(100) as u32) / 100;
+                target.saturating_sub(carried)
+            }
+        };
+
+        let amount = requested.min(planet_available(planet, t.cargo_type)).min(f.free_cargo_space());
+        if amount == 0 {
+            continue;
+        }
+
+        planet_remove(planet, t.cargo_type, amount);
+        *f.cargo.get_mut(t.cargo_type) += amount;
+
+        let (beamed, parameters) = transfer_message_parameters(&name, t.cargo_type, amount, &planet.name);
+        let message_id = if beamed { MessageId::CargoBeamedFrom } else { MessageId::CargoLoaded };
+        messages.push((message_id, parameters));
+    }
+
+    send_messages(universe, owner_id, messages);
+}
+
+/*
+    Unloads cargo onto the planet at the fleet's waypoint.  Minerals may be
+    dropped on any planet.  Uninhabited worlds must be colonized first.
+    Colonists beamed down onto another player's planet invade it, unless
+    the planet belongs to a friend or has a starbase overhead; the
+    invasions are fought once every fleet has unloaded.  When the waypoint
+    is a fleet, the cargo is handed over to that fleet.
+*/
+pub fn unload_cargo(universe: &mut Universe, fleet_id: u32, transfers: &[CargoTransfer], assaults: &mut Vec<GroundAssault>) {
+    if let Some((owner_id, target_fleet_id)) = waypoint_fleet(universe, fleet_id) {
+        universe.transfer_cargo(owner_id, fleet_id, target_fleet_id, transfers);
+        return;
+    }
+
+    let planet_index = match waypoint_planet_index(universe, fleet_id) {
+        Some(i) => i,
+        None => return
+    };
+
+    let (owner_id, name) = {
+        let f = &universe.fleets[&fleet_id];
+        (f.owner_id, f.name.clone())
+    };
+
+    let mut messages = Vec::new();
+
+    for t in transfers.iter() {
+        let planet = &mut universe.planets[planet_index];
+
+        if t.cargo_type == CargoType::Colonists && planet.owner_id != owner_id {
+            match (planet.owner_id, owner_id) {
+                (None, _) => messages.push((MessageId::ColonistsBeamedToUninhabited, vec![name.clone(), planet.name.clone()])),
+                (Some(defender_id), Some(attacker_id)) => {
+                    let friendly = universe.players[attacker_id as usize].relation_to(defender_id) == PlayerRelation::Friend;
+                    if friendly || planet.related_starbase.is_some() {
+                        messages.push((MessageId::ColonistTransferToPlayerRefused, vec![name.clone()]));
+                        continue;
+                    }
+
+                    let f = universe.fleets.get_mut(&fleet_id).unwrap();
+                    let amount = unload_request(f, t);
+
+                    if amount > 0 {
+                        f.cargo.colonists -= amount;
+                        assaults.push(GroundAssault { planet_id: planet.id, owner_id: attacker_id, troops: amount * COLONISTS_PER_KT });
+                    }
+                },
+                (Some(_), None) => messages.push((MessageId::ColonistTransferToPlayerRefused, vec![name.clone()]))
+            }
+            continue;
+        }
+
+        let f = universe.fleets.get_mut(&fleet_id).unwrap();
+        let amount = unload_request(f, t).min(planet_space(planet, t.cargo_type));
+        if amount == 0 {
+            continue;
+        }
+
+        *f.cargo.get_mut(t.cargo_type) -= amount;
+        planet_add(planet, t.cargo_type, amount);
+
+        let (beamed, parameters) = transfer_message_parameters(&name, t.cargo_type, amount, &planet.name);
+        let message_id = if beamed { MessageId::CargoBeamedTo } else { MessageId::CargoUnloaded };
+        messages.push((message_id, parameters));
+    }
+
+    send_messages(universe, owner_id, messages);
+}
+
+/*
+    How much of a cargo type the fleet wants to unload: a fixed amount, all
+    of it, or whatever is above the given percent of its hold.
+*/
+fn unload_request(f: &Fleet, t: &CargoTransfer) -> u32 {
+    let carried = f.cargo.get(t.cargo_type);
+    let requested = match t.amount {
+        TransferAmount::Amount(a) => a,
+        TransferAmount::All => carried,
+        TransferAmount::FillPercent(percent) => {
+            let target = f.total_cargo_capacity * (percent.min(100) as u32) / 100;
+            carried.saturating_sub(target)
+        }
+    };
+
+    requested.min(carried)
+}
+
+fn send_messages(universe: &mut Universe, owner_id: Option<u8>, messages: Vec<(MessageId, Vec<String>)>) {
+    if let Some(pid) = owner_id {
+        let player = &mut universe.players[pid as usize];
+        for (message_id, parameters) in messages {
+            player.add_message(message_id, parameters);
+        }
+    }
+}
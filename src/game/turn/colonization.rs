@@ -0,0 +1,136 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::COLONISTS_PER_KT;
+use ::game::objects::tech::TechnologyId;
+use ::game::objects::race::PrimaryRacialTrait;
+use ::game::objects::message::MessageId;
+use ::game::objects::predefined::fleets::construct_starter_colony_design;
+use ::game::objects::predefined::fleets::STARTER_COLONY_NAME;
+
+// Share of a colony ship's minerals recovered when it is dismantled
+pub const COLONY_SHIP_SALVAGE_PERCENT : u32 = 75;
+
+/*
+    Carries out a colonize order.  The fleet must orbit an unowned planet,
+    carry colonists and have a ship with a colonization module.  The whole
+    fleet is then dismantled: its colonists settle the planet, and its cargo
+    and part of the ships' minerals are left on the surface.
+
+    Alternate Reality races colonize with the Orbital Construction Module and
+    get a starter colony starbase instead of living on the surface.
+*/
+pub fn colonize(universe: &mut Universe, fleet_id: u32) {
+    let (owner_id, name, location, colonists) = match universe.fleets.get(&fleet_id) {
+        Some(f) => match f.owner_id {
+            Some(o) => (o, f.name.clone(), f.location.clone(), f.cargo.colonists),
+            None => return
+        },
+        None => return
+    };
+
+    let planet_index = match universe.planets.iter().position(|p| p.location.is_same_location(&location)) {
+        Some(i) => i,
+        None => {
+            universe.players[owner_id as usize].add_message(MessageId::ColonizeNotInOrbit, vec![name]);
+            return;
+        }
+    };
+
+    let planet_name = universe.planets[planet_index].name.clone();
+
+    if universe.planets[planet_index].owner_id.is_some() || universe.planets[planet_index].population > 0 {
+        universe.players[owner_id as usize].add_message(MessageId::ColonizeAlreadyPopulated, vec![name, planet_name]);
+        return;
+    }
+
+    if colonists == 0 {
+        universe.players[owner_id as usize].add_message(MessageId::ColonizeNoColonists, vec![name, planet_name]);
+        return;
+    }
+
+    let mut has_module = false;
+    let mut minerals = (0, 0, 0);
+    for m in universe.fleets[&fleet_id].members.iter() {
+        if let Some(d) = universe.lookup_ship_design(m.design_id) {
+            if d.count_slot_technology(TechnologyId::ColonizationModule) > 0 || d.count_slot_technology(TechnologyId::OrbitalConstructionModule) > 0 {
+                has_module = true;
+            }
+
+            let cost = d.calculate_cost();
+            let quantity = m.quantity as u32;
+            minerals.0 += cost.ironium * quantity;
+            minerals.1 += cost.boranium * quantity;
+            minerals.2 += cost.germanium * quantity;
+        }
+    }
+
+    if !has_module {
+        universe.players[owner_id as usize].add_message(MessageId::ColonizeNoModule, vec![name.clone(), planet_name, name]);
+        return;
+    }
+
+    let fleet = match universe.remove_fleet(fleet_id) {
+        Some(f) => f,
+        None => return
+    };
+
+    {
+        let planet = &mut universe.planets[planet_index];
+        planet.owner_id = Some(owner_id);
+        planet.population = colonists * COLONISTS_PER_KT;
+        planet.has_ever_been_colonized = true;
+        planet.production_queue.clear();
+
+        planet.on_surface.add_saturating(
+            fleet.cargo.ironium + minerals.0 * COLONY_SHIP_SALVAGE_PERCENT / 100,
+            fleet.cargo.boranium + minerals.1 * COLONY_SHIP_SALVAGE_PERCENT / 100,
+            fleet.cargo.germanium + minerals.2 * COLONY_SHIP_SALVAGE_PERCENT / 100);
+    }
+
+    let planet_id = universe.planets[planet_index].id;
+    let player = &mut universe.players[owner_id as usize];
+
+    if player.race.primary_racial_trait == PrimaryRacialTrait::AlternateReality {
+        let existing = player.starbase_designs.iter()
+            .filter_map(|d| d.as_ref())
+            .find(|d| d.name == STARTER_COLONY_NAME)
+            .cloned();
+
+        let design = match existing {
+            Some(d) => Some(d),
+            None => {
+                let index = player.add_starbase_design(construct_starter_colony_design());
+                player.starbase_designs.get(index as usize).and_then(|d| d.clone())
+            }
+        };
+
+        player.add_message(MessageId::ColonizeStarterColony, vec![planet_name]);
+
+        if let Some(d) = design {
+            universe.add_starbase_at_planet(&d, owner_id, planet_id);
+        }
+    }
+    else {
+        player.add_message(MessageId::ColonizeSuccess, vec![planet_name]);
+    }
+}
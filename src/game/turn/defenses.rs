@@ -0,0 +1,63 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::message::MessageId;
+
+/*
+    Planets use the best defense technology their owner has learned.  When
+    a better defense is learned, the defenses already built on every planet
+    are refitted with it and the owner is told which planets were upgraded.
+*/
+pub fn upgrade_planetary_defenses(universe: &mut Universe) {
+    let mut messages = Vec::new();
+
+    for planet in universe.planets.iter_mut() {
+        let owner_id = match planet.owner_id {
+            Some(o) => o,
+            None => continue
+        };
+
+        let best = match universe.players[owner_id as usize].get_best_defense_quality() {
+            Some(q) => q,
+            None => continue
+        };
+
+        let upgraded = match planet.defense_quality {
+            Some(ref current) => best > *current,
+            None => true
+        };
+
+        if !upgraded {
+            continue;
+        }
+
+        if planet.defenses > 0 {
+            messages.push((owner_id, vec![planet.defenses.to_string(), planet.name.clone(), best.name().to_string()]));
+        }
+
+        planet.defense_quality = Some(best);
+    }
+
+    for (owner_id, parameters) in messages {
+        universe.players[owner_id as usize].add_message(MessageId::DefensesUpgraded, parameters);
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::planet::HabitatLevel;
+use ::game::objects::tech::RESEARCH_FIELD_NAMES;
+use ::game::objects::message::MessageId;
+
+// Chance each year that a comet strikes somewhere in the universe
+pub const COMET_CHANCE : f64 = 0.02;
+
+// Chance that the first colonists on a planet find an ancient artifact
+pub const ARTIFACT_CHANCE : f64 = 0.05;
+pub const ARTIFACT_MIN_RESOURCES : u32 = 100;
+pub const ARTIFACT_MAX_RESOURCES : u32 = 1000;
+
+// Chance each year that a moving fleet's engines refuse to start
+pub const BALKY_ENGINE_CHANCE : f64 = 0.005;
+
+pub const MAX_HABITAT_VALUE : u8 = 100;
+
+#[derive(Clone, Copy)]
+pub enum CometSize {
+    Small,
+    Medium,
+    Large,
+    Huge
+}
+
+impl CometSize {
+    // kT of each mineral left on the surface and the rise in concentration
+    fn minerals(&self) -> (u32, u16) {
+        match *self {
+            CometSize::Small => (50, 10),
+            CometSize::Medium => (150, 20),
+            CometSize::Large => (300, 35),
+            CometSize::Huge => (600, 50)
+        }
+    }
+
+    // How many habitat values change, and by up to how many clicks
+    fn habitat_change(&self) -> (usize, u8) {
+        match *self {
+            CometSize::Small => (1, 5),
+            CometSize::Medium => (2, 10),
+            CometSize::Large => (3, 15),
+            CometSize::Huge => (3, 25)
+        }
+    }
+
+    fn kill_percent(&self) -> u32 {
+        match *self {
+            CometSize::Small => 25,
+            CometSize::Medium => 45,
+            CometSize::Large => 65,
+            CometSize::Huge => 85
+        }
+    }
+
+    fn messages(&self) -> (MessageId, MessageId) {
+        match *self {
+            CometSize::Small => (MessageId::CometSmall, MessageId::CometSmallOwned),
+            CometSize::Medium => (MessageId::CometMedium, MessageId::CometMediumOwned),
+            CometSize::Large => (MessageId::CometLarge, MessageId::CometLargeOwned),
+            CometSize::Huge => (MessageId::CometHuge, MessageId::CometHugeOwned)
+        }
+    }
+}
+
+/*
+    Each moving fleet has a small chance of its engines balking, leaving
+    it where it is for the year.  Returns the fleets that cannot move.
+*/
+pub fn balk_engines(universe: &mut Universe, rng: &mut StdRng) -> Vec<u32> {
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+
+    let mut stalled = Vec::new();
+    for fid in fleet_ids {
+        let (owner_id, name) = {
+            let f = &universe.fleets[&fid];
+            match (f.owner_id, f.next_waypoint()) {
+                (Some(o), Some(w)) if w.warp > 0 => (o, f.name.clone()),
+                _ => continue
+            }
+        };
+
+        if rng.gen_bool(BALKY_ENGINE_CHANCE) {
+            universe.players[owner_id as usize].add_message(MessageId::BalkyEngines, vec![name]);
+            stalled.push(fid);
+        }
+    }
+
+    stalled
+}
+
+/*
+    The first colonists to settle a planet sometimes find an artifact left
+    by an ancient race, which adds resources to a random research field.
+    The planets given are those that had never been colonized before this
+    year's waypoint tasks.
+*/
+pub fn find_artifacts(universe: &mut Universe, rng: &mut StdRng, unsettled: &[u32]) {
+    for planet_id in unsettled.iter() {
+        let (owner_id, name) = match universe.get_planet(*planet_id) {
+            Some(p) if p.has_ever_been_colonized => match p.owner_id {
+                Some(o) => (o, p.name.clone()),
+                None => continue
+            },
+            _ => continue
+        };
+
+        if !rng.gen_bool(ARTIFACT_CHANCE) {
+            continue;
+        }
+
+        let field = rng.gen_range(0, RESEARCH_FIELD_NAMES.len());
+        let resources = rng.gen_range(ARTIFACT_MIN_RESOURCES, ARTIFACT_MAX_RESOURCES + 1);
+
+        let player = &mut universe.players[owner_id as usize];
+        player.tech_progress[field] += resources;
+        player.add_message(MessageId::ArtifactFound, vec![name, RESEARCH_FIELD_NAMES[field].to_string(), resources.to_string()]);
+    }
+}
+
+/*
+    Now and then a comet strikes a random planet.  Larger comets are
+    rarer:
+
+        small 50%, medium 30%, large 15%, huge 5%
+
+    Each brings minerals, both on the surface and in the planet's
+    concentrations, and shifts one or more of its habitat values.  Comets
+    that hit an inhabited planet kill a share of its colonists.
+*/
+pub fn strike_comets(universe: &mut Universe, rng: &mut StdRng) {
+    if universe.planets.is_empty() || !rng.gen_bool(COMET_CHANCE) {
+        return;
+    }
+
+    let size = match rng.gen_range(0, 100) {
+        0..=49 => CometSize::Small,
+        50..=79 => CometSize::Medium,
+        80..=94 => CometSize::Large,
+        _ => CometSize::Huge
+    };
+
+    let index = rng.gen_range(0, universe.planets.len());
+    let (surface, concentration) = size.minerals();
+    let (changes, max_clicks) = size.habitat_change();
+
+    let mut dimensions = vec!["temperature", "gravity", "radiation"];
+    dimensions.shuffle(rng);
+    dimensions.truncate(changes);
+
+    let shifts : Vec<i32> = dimensions.iter()
+        .map(|_| {
+            let clicks = rng.gen_range(1, max_clicks as i32 + 1);
+            if rng.gen_bool(0.5) { clicks } else { -clicks }
+        })
+        .collect();
+
+    let p = &mut universe.planets[index];
+    p.on_surface.add_saturating(surface, surface, surface);
+    p.mineral_concentration.ironium = p.mineral_concentration.ironium.saturating_add(concentration);
+    p.mineral_concentration.boranium = p.mineral_concentration.boranium.saturating_add(concentration);
+    p.mineral_concentration.germanium = p.mineral_concentration.germanium.saturating_add(concentration);
+
+    for (dimension, by) in dimensions.iter().zip(shifts.iter()) {
+        shift_habitat(&mut p.habitat, dimension, *by);
+
+        // The comet changes the planet itself, so undoing terraforming
+        // later returns to the new values
+        if let Some(ref mut original) = p.original_habitat {
+            shift_habitat(original, dimension, *by);
+        }
+    }
+
+    let (unowned_message, owned_message) = size.messages();
+    match p.owner_id {
+        Some(owner_id) => {
+            p.population -= p.population * size.kill_percent() / 100;
+
+            let mut parameters = vec![p.name.clone()];
+            parameters.extend(dimensions.iter().map(|d| d.to_string()));
+            universe.players[owner_id as usize].add_message(owned_message, parameters);
+        },
+        None => {
+            let name = p.name.clone();
+            for player in universe.players.iter_mut() {
+                player.add_message(unowned_message, vec![name.clone()]);
+            }
+        }
+    }
+}
+
+fn shift_habitat(habitat: &mut HabitatLevel, dimension: &str, by: i32) {
+    let shift = |value: u8| -> u8 { (value as i32 + by).clamp(0, MAX_HABITAT_VALUE as i32) as u8 };
+
+    match dimension {
+        "temperature" => habitat.temperature = shift(habitat.temperature),
+        "gravity" => habitat.gravity = shift(habitat.gravity),
+        _ => habitat.radiation = shift(habitat.radiation)
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::game::Game;
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::ShipOrderType;
+use ::game::objects::fleet::WaypointTarget;
+use ::game::objects::order::PlayerOrders;
+use ::game::objects::message::MessageId;
+use ::game::turn::movement::move_fleets;
+use ::game::turn::cargo::load_cargo;
+use ::game::turn::cargo::unload_cargo;
+use ::game::turn::colonization::colonize;
+use ::game::turn::scrapping::scrap_fleet;
+use ::game::turn::mining::remote_mine_planets;
+use ::game::turn::minefields::lay_mines;
+use ::game::turn::minefields::sweep_minefields;
+use ::game::turn::minefields::decay_minefields;
+use ::game::turn::minefields::update_minefield_detection;
+use ::game::turn::repair::repair_fleets;
+use ::game::turn::repair::repair_starbases;
+use ::game::turn::patrol::patrol_fleets;
+use ::game::turn::battle::resolve_battles;
+use ::game::turn::bombing::bomb_planets;
+use ::game::turn::defenses::upgrade_planetary_defenses;
+use ::game::turn::invasion::resolve_colonization_races;
+use ::game::turn::invasion::invade_planets;
+use ::game::turn::packets::move_packets;
+use ::game::turn::events::balk_engines;
+use ::game::turn::events::find_artifacts;
+use ::game::turn::events::strike_comets;
+use ::game::turn::trader::move_mystery_traders;
+use ::game::turn::trader::trade_with_mystery_traders;
+use ::game::turn::wormholes::shift_wormholes;
+
+/*
+    Advances the game by one year.  Messages from the previous year are
+    cleared first so that each player only sees what happened this turn,
+    then the players' orders are carried out in player order.  Random
+    events only happen in games that allow them.
+*/
+pub fn generate_turn(game: &mut Game, orders: &[PlayerOrders]) {
+    let mut rng = game.turn_rng();
+
+    for p in game.universe.players.iter_mut() {
+        p.messages.clear();
+    }
+
+    for player_orders in orders.iter() {
+        for order in player_orders.orders.iter() {
+            order.execute(&mut game.universe, player_orders.player_id);
+        }
+    }
+
+    upgrade_planetary_defenses(&mut game.universe);
+
+    let random_events = game.parameters.allow_random_events;
+    let stalled = if random_events { balk_engines(&mut game.universe, &mut rng) } else { Vec::new() };
+    if random_events {
+        move_mystery_traders(&mut game.universe, &mut rng, game.year);
+    }
+
+    patrol_fleets(&mut game.universe);
+    let arrived = move_fleets(&mut game.universe, &mut rng, &stalled);
+    game.battles = resolve_battles(&mut game.universe, &mut rng);
+    bomb_planets(&mut game.universe);
+    move_packets(&mut game.universe);
+
+    let unsettled : Vec<u32> = game.universe.planets.iter()
+        .filter(|p| !p.has_ever_been_colonized)
+        .map(|p| p.id)
+        .collect();
+    execute_waypoint_tasks(&mut game.universe, &arrived);
+    trade_with_mystery_traders(&mut game.universe, &mut rng);
+    if random_events {
+        find_artifacts(&mut game.universe, &mut rng, &unsettled);
+    }
+    remote_mine_planets(&mut game.universe);
+
+    lay_mines(&mut game.universe);
+    sweep_minefields(&mut game.universe);
+    decay_minefields(&mut game.universe);
+    update_minefield_detection(&mut game.universe);
+
+    repair_fleets(&mut game.universe);
+    repair_starbases(&mut game.universe);
+
+    if random_events {
+        strike_comets(&mut game.universe, &mut rng);
+    }
+    shift_wormholes(&mut game.universe, &mut rng);
+
+    game.year += 1;
+}
+
+/*
+    Carries out the task of the waypoint each fleet has just reached.  A
+    waypoint in the fleet's current position counts as reached as soon as
+    the fleet is given a warp speed for it.  Remote mining is not handled
+    here since it carries on every year the fleet stays put.  Races for the
+    same uninhabited planet are settled before anyone colonizes, and
+    invasions are fought after everyone has unloaded.
+*/
+pub fn execute_waypoint_tasks(universe: &mut Universe, fleet_ids: &[u32]) {
+    let mut assaults = Vec::new();
+
+    resolve_colonization_races(universe, fleet_ids);
+
+    for fid in fleet_ids.iter() {
+        let task = match universe.fleets.get(fid) {
+            Some(f) => f.orders[0].task.clone(),
+            None => continue
+        };
+
+        match task {
+            ShipOrderType::Load(transfers) => load_cargo(universe, *fid, &transfers),
+            ShipOrderType::Unload(transfers) => unload_cargo(universe, *fid, &transfers, &mut assaults),
+            ShipOrderType::Colonize => colonize(universe, *fid),
+            ShipOrderType::Scrap => scrap_fleet(universe, *fid),
+            ShipOrderType::Merge => merge_at_waypoint(universe, *fid),
+            ShipOrderType::Transfer(to_player_id) => {
+                if let Some(owner_id) = universe.fleets[fid].owner_id {
+                    universe.transfer_fleet(owner_id, *fid, to_player_id);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    invade_planets(universe, &assaults);
+}
+
+fn merge_at_waypoint(universe: &mut Universe, fleet_id: u32) {
+    let (owner_id, name, target) = match universe.fleets.get(&fleet_id) {
+        Some(f) => match f.owner_id {
+            Some(o) => (o, f.name.clone(), f.orders[0].target.clone()),
+            None => return
+        },
+        None => return
+    };
+
+    let target_fleet_id = match target {
+        WaypointTarget::Fleet(tid) if universe.fleets.contains_key(&tid) => tid,
+        _ => {
+            universe.players[owner_id as usize].add_message(MessageId::MergeTargetNotFleet, vec![name]);
+            return;
+        }
+    };
+
+    if universe.fleets[&target_fleet_id].owner_id != Some(owner_id) {
+        universe.players[owner_id as usize].add_message(MessageId::MergeTargetNotOwned, vec![name]);
+        return;
+    }
+
+    universe.merge_fleets(owner_id, fleet_id, target_fleet_id);
+}
@@ -0,0 +1,469 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::ShipOrderType;
+use ::game::objects::fleet::COLONISTS_PER_KT;
+use ::game::objects::race::Race;
+use ::game::objects::race::PrimaryRacialTrait;
+use ::game::objects::message::MessageId;
+
+// Troops landing on a planet fight 10% better than its defenders
+pub const ATTACKER_TROOP_FACTOR : f64 = 1.1;
+pub const WAR_MONGER_ATTACK_FACTOR : f64 = 1.5;
+pub const INNER_STRENGTH_TROOP_FACTOR : f64 = 1.2;
+
+/*
+    Colonists beamed down onto a foreign planet this year, counted in
+    colonists rather than kT.
+*/
+pub struct GroundAssault {
+    pub planet_id: u32,
+    pub owner_id: u8,
+    pub troops: u32
+}
+
+// One player's troops on the ground: owner, troops and fighting factor
+struct GroundForce {
+    owner_id: u8,
+    troops: u32,
+    factor: f64
+}
+
+impl GroundForce {
+    fn strength(&self) -> f64 {
+        self.troops as f64 * self.factor
+    }
+}
+
+/*
+    Attacking troops get a 10% bonus and War Monger troops half again on
+    top of that.  Inner Strength colonists fight 20% better, attacking or
+    defending.
+*/
+fn troop_factor(race: &Race, attacking: bool) -> f64 {
+    let mut factor = 1.0;
+
+    if attacking {
+        factor *= ATTACKER_TROOP_FACTOR;
+        if race.primary_racial_trait == PrimaryRacialTrait::WarMonger {
+            factor *= WAR_MONGER_ATTACK_FACTOR;
+        }
+    }
+
+    if race.primary_racial_trait == PrimaryRacialTrait::InnerStrength {
+        factor *= INNER_STRENGTH_TROOP_FACTOR;
+    }
+
+    factor
+}
+
+/*
+    Every force fights every other at once.  The strongest force wins and
+    keeps what is left after matching the next strongest:
+
+        survivors = (strongest - second strongest) / winner's factor
+
+    Returns the winner and its surviving colonists, or None when the top
+    forces wipe each other out.
+*/
+fn ground_combat(forces: &[GroundForce]) -> Option<(u8, u32)> {
+    let mut ranked : Vec<&GroundForce> = forces.iter().collect();
+    ranked.sort_by(|a, b| b.strength().partial_cmp(&a.strength()).unwrap().then(a.owner_id.cmp(&b.owner_id)));
+
+    let winner = ranked.first()?;
+    let opposition = ranked.get(1).map_or(0.0, |f| f.strength());
+    let survivors = ((winner.strength() - opposition) / winner.factor).floor() as u32;
+
+    if survivors == 0 {
+        None
+    }
+    else {
+        Some((winner.owner_id, survivors))
+    }
+}
+
+/*
+    Settles the planets that more than one player tried to colonize in the
+    same year.  The colonists of each player fight it out on the surface;
+    the winner's colony fleets go on to colonize with the survivors, the
+    losers' colonists are lost and their colonize orders are cancelled.
+*/
+pub fn resolve_colonization_races(universe: &mut Universe, fleet_ids: &[u32]) {
+    let mut races : Vec<(u32, Vec<u32>)> = Vec::new();
+
+    for fid in fleet_ids.iter() {
+        let f = match universe.fleets.get(fid) {
+            Some(f) if f.owner_id.is_some() && f.cargo.colonists > 0 && matches!(f.orders[0].task, ShipOrderType::Colonize) => f,
+            _ => continue
+        };
+
+        let planet_id = match universe.planets.iter().find(|p| p.location.is_same_location(&f.location)) {
+            Some(p) if p.owner_id.is_none() && p.population == 0 => p.id,
+            _ => continue
+        };
+
+        match races.iter_mut().find(|r| r.0 == planet_id) {
+            Some(r) => r.1.push(*fid),
+            None => races.push((planet_id, vec![*fid]))
+        }
+    }
+
+    for (planet_id, fids) in races {
+        let mut forces : Vec<GroundForce> = Vec::new();
+        for fid in fids.iter() {
+            let f = &universe.fleets[fid];
+            let owner_id = f.owner_id.unwrap();
+            let troops = f.cargo.colonists * COLONISTS_PER_KT;
+
+            match forces.iter_mut().find(|g| g.owner_id == owner_id) {
+                Some(g) => g.troops += troops,
+                None => forces.push(GroundForce {
+                    owner_id,
+                    troops,
+                    factor: troop_factor(&universe.players[owner_id as usize].race, true)
+                })
+            }
+        }
+
+        if forces.len() < 2 {
+            continue;
+        }
+
+        let planet_name = universe.planets.iter().find(|p| p.id == planet_id).unwrap().name.clone();
+        let ways = forces.len().to_string();
+        let result = ground_combat(&forces);
+
+        for fid in fids.iter() {
+            let f = universe.fleets.get_mut(fid).unwrap();
+            let owner_id = f.owner_id.unwrap();
+
+            match result {
+                Some((winner, survivors)) if winner == owner_id => {
+                    let troops = forces.iter().find(|g| g.owner_id == winner).unwrap().troops;
+                    f.cargo.colonists = ((f.cargo.colonists as u64 * survivors as u64 / troops as u64) as u32).max(1);
+                },
+                _ => {
+                    f.cargo.colonists = 0;
+                    f.orders[0].task = ShipOrderType::NoTask;
+                }
+            }
+        }
+
+        for g in forces.iter() {
+            let message = match result {
+                Some((winner, _)) if winner == g.owner_id => (MessageId::ColonizationRaceWon, vec![ways.clone(), planet_name.clone()]),
+                Some((winner, _)) => {
+                    let race = universe.players[winner as usize].race.plural_name.clone();
+                    (MessageId::ColonizationRaceLost, vec![ways.clone(), planet_name.clone(), race])
+                },
+                None => (MessageId::MultiAssaultNobodySurvived, vec![ways.clone(), planet_name.clone()])
+            };
+
+            universe.players[g.owner_id as usize].add_message(message.0, message.1);
+        }
+    }
+}
+
+/*
+    Resolves the colonists dropped onto enemy planets this year.  The
+    planet's defenses shoot down a share of each player's troops as they
+    land (see Planet::invasion_defense_coverage), then the survivors and
+    the planet's colonists fight it out.  An attacker that wins takes the
+    planet and everything built on it.
+*/
+pub fn invade_planets(universe: &mut Universe, assaults: &[GroundAssault]) {
+    let mut planet_ids : Vec<u32> = Vec::new();
+    for a in assaults.iter() {
+        if !planet_ids.contains(&a.planet_id) {
+            planet_ids.push(a.planet_id);
+        }
+    }
+
+    for planet_id in planet_ids {
+        let index = match universe.planets.iter().position(|p| p.id == planet_id) {
+            Some(i) => i,
+            None => continue
+        };
+
+        let defender_id = match universe.planets[index].owner_id {
+            Some(o) => o,
+            None => continue
+        };
+
+        let planet_name = universe.planets[index].name.clone();
+        let coverage = universe.planets[index].invasion_defense_coverage();
+
+        // Attacking owner, troops landed and troops shot down
+        let mut attackers : Vec<(u8, u32, u32)> = Vec::new();
+        for a in assaults.iter().filter(|a| a.planet_id == planet_id) {
+            match attackers.iter_mut().find(|t| t.0 == a.owner_id) {
+                Some(t) => t.1 += a.troops,
+                None => attackers.push((a.owner_id, a.troops, 0))
+            }
+        }
+        for t in attackers.iter_mut() {
+            t.2 = (t.1 as f64 * coverage).round() as u32;
+        }
+
+        let mut forces = vec![GroundForce {
+            owner_id: defender_id,
+            troops: universe.planets[index].population,
+            factor: troop_factor(&universe.players[defender_id as usize].race, false)
+        }];
+        for t in attackers.iter() {
+            forces.push(GroundForce {
+                owner_id: t.0,
+                troops: t.1 - t.2,
+                factor: troop_factor(&universe.players[t.0 as usize].race, true)
+            });
+        }
+
+        let result = ground_combat(&forces);
+        let defender_race = universe.players[defender_id as usize].race.plural_name.clone();
+        let mut messages : Vec<(u8, MessageId, Vec<String>)> = Vec::new();
+
+        match result {
+            Some((winner, survivors)) if winner == defender_id => {
+                universe.planets[index].population = survivors;
+
+                for t in attackers.iter() {
+                    let attacker_race = universe.players[t.0 as usize].race.plural_name.clone();
+                    if t.2 > 0 {
+                        messages.push((t.0, MessageId::InvasionShotDownAndMassacred, vec![t.1.to_string(), planet_name.clone(), t.2.to_string(), defender_race.clone()]));
+                        messages.push((defender_id, MessageId::InvasionRepelledWithDefenses, vec![planet_name.clone(), t.1.to_string(), attacker_race]));
+                    }
+                    else {
+                        messages.push((t.0, MessageId::InvasionMassacred, vec![t.1.to_string(), planet_name.clone(), defender_race.clone()]));
+                        messages.push((defender_id, MessageId::InvasionRepelled, vec![planet_name.clone(), t.1.to_string(), attacker_race]));
+                    }
+                }
+            },
+            Some((winner, survivors)) => {
+                let best_defense = universe.players[winner as usize].get_best_defense_quality();
+                {
+                    let planet = &mut universe.planets[index];
+                    planet.owner_id = Some(winner);
+                    planet.population = survivors;
+                    planet.production_queue.clear();
+                    planet.defense_quality = best_defense;
+                }
+
+                let attacker_race = universe.players[winner as usize].race.plural_name.clone();
+                let landed = attackers.iter().find(|t| t.0 == winner).map_or(0, |t| t.1);
+                messages.push((defender_id, MessageId::PlanetInvaded, vec![attacker_race, planet_name.clone(), landed.to_string()]));
+
+                for t in attackers.iter() {
+                    if t.0 == winner {
+                        messages.push((t.0, MessageId::InvasionSucceeded, vec![universe.players[defender_id as usize].race.name.clone(), planet_name.clone()]));
+                    }
+                    else {
+                        messages.push((t.0, MessageId::InvasionTroopsDestroyed, vec![planet_name.clone()]));
+                    }
+                }
+            },
+            None => {
+                {
+                    let planet = &mut universe.planets[index];
+                    planet.owner_id = None;
+                    planet.population = 0;
+                    planet.production_queue.clear();
+                }
+
+                // With a single attacker the defender is crushed and the
+                // attacking troops die with them; the multi-way messages
+                // are for free-for-alls
+                if let [ref t] = attackers[..] {
+                    let attacker_race = universe.players[t.0 as usize].race.plural_name.clone();
+                    messages.push((defender_id, MessageId::PlanetInvaded, vec![attacker_race, planet_name.clone(), t.1.to_string()]));
+                    messages.push((t.0, MessageId::InvasionTroopsDestroyed, vec![planet_name.clone()]));
+                }
+                else {
+                    let ways = forces.len().to_string();
+                    messages.push((defender_id, MessageId::MultiInvasionAllKilled, vec![attackers.len().to_string(), planet_name.clone()]));
+                    for t in attackers.iter() {
+                        messages.push((t.0, MessageId::MultiAssaultNobodySurvived, vec![ways.clone(), planet_name.clone()]));
+                    }
+                }
+            }
+        }
+
+        for (pid, message_id, parameters) in messages {
+            universe.players[pid as usize].add_message(message_id, parameters);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::game::objects::player::Player;
+    use ::game::objects::fleet::ShipDesign;
+    use ::game::objects::tech::TechnologyId;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::tutorial::generate_tutorial_universe;
+
+    // The tutorial map with Humanoid players at war; player 0 owns the
+    // first planet with the given colonists and no defenses
+    fn invasion_universe(players: u8, population: u32) -> Universe {
+        let mut u = generate_tutorial_universe();
+        for pid in 0..players {
+            let mut player = Player::construct_from_race(create_humanoid());
+            player.race.primary_racial_trait = PrimaryRacialTrait::JackOfAllTrades;
+            player.id = pid;
+            u.players.push(player);
+        }
+
+        let planet = &mut u.planets[0];
+        planet.owner_id = Some(0);
+        planet.population = population;
+        planet.defenses = 0;
+        u
+    }
+
+    fn assault(u: &Universe, owner_id: u8, troops: u32) -> GroundAssault {
+        GroundAssault { planet_id: u.planets[0].id, owner_id, troops }
+    }
+
+    fn message_ids(u: &Universe, player_id: u8) -> Vec<u16> {
+        u.players[player_id as usize].messages.iter().map(|m| m.message_id).collect()
+    }
+
+    #[test]
+    fn attackers_fight_better_and_war_mongers_and_inner_strength_more_so() {
+        let mut race = create_humanoid();
+        race.primary_racial_trait = PrimaryRacialTrait::JackOfAllTrades;
+        assert_eq!(troop_factor(&race, false), 1.0);
+        assert!((troop_factor(&race, true) - 1.1).abs() < 1e-9);
+
+        race.primary_racial_trait = PrimaryRacialTrait::WarMonger;
+        assert_eq!(troop_factor(&race, false), 1.0);
+        assert!((troop_factor(&race, true) - 1.65).abs() < 1e-9);
+
+        race.primary_racial_trait = PrimaryRacialTrait::InnerStrength;
+        assert!((troop_factor(&race, false) - 1.2).abs() < 1e-9);
+        assert!((troop_factor(&race, true) - 1.32).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_strongest_force_keeps_what_outlasts_the_next_strongest() {
+        let forces = vec![
+            GroundForce { owner_id: 0, troops: 1000, factor: 1.0 },
+            GroundForce { owner_id: 1, troops: 2000, factor: 1.1 },
+            GroundForce { owner_id: 2, troops: 500, factor: 1.1 }
+        ];
+        // (2200 - 1000) / 1.1
+        assert_eq!(ground_combat(&forces), Some((1, 1090)));
+
+        let even = vec![
+            GroundForce { owner_id: 0, troops: 1100, factor: 1.0 },
+            GroundForce { owner_id: 1, troops: 1000, factor: 1.1 }
+        ];
+        assert_eq!(ground_combat(&even), None);
+    }
+
+    #[test]
+    fn a_stronger_invasion_takes_the_planet() {
+        let mut u = invasion_universe(2, 1000);
+        let assaults = vec![assault(&u, 1, 2000)];
+
+        invade_planets(&mut u, &assaults);
+        assert_eq!((u.planets[0].owner_id, u.planets[0].population), (Some(1), 1090));
+        assert_eq!(message_ids(&u, 0), vec![MessageId::PlanetInvaded as u16]);
+        assert_eq!(message_ids(&u, 1), vec![MessageId::InvasionSucceeded as u16]);
+    }
+
+    #[test]
+    fn a_weaker_invasion_is_massacred() {
+        let mut u = invasion_universe(2, 2000);
+        let assaults = vec![assault(&u, 1, 1000)];
+
+        invade_planets(&mut u, &assaults);
+        assert_eq!((u.planets[0].owner_id, u.planets[0].population), (Some(0), 900));
+        assert_eq!(message_ids(&u, 0), vec![MessageId::InvasionRepelled as u16]);
+        assert_eq!(message_ids(&u, 1), vec![MessageId::InvasionMassacred as u16]);
+    }
+
+    #[test]
+    fn evenly_matched_invaders_and_defenders_leave_the_planet_empty() {
+        let mut u = invasion_universe(2, 1100);
+        let assaults = vec![assault(&u, 1, 1000)];
+
+        invade_planets(&mut u, &assaults);
+        assert_eq!((u.planets[0].owner_id, u.planets[0].population), (None, 0));
+        assert_eq!(message_ids(&u, 0), vec![MessageId::PlanetInvaded as u16]);
+        assert_eq!(message_ids(&u, 1), vec![MessageId::InvasionTroopsDestroyed as u16]);
+    }
+
+    #[test]
+    fn the_strongest_of_several_invaders_takes_the_planet() {
+        let mut u = invasion_universe(3, 500);
+        let assaults = vec![assault(&u, 1, 2000), assault(&u, 2, 400)];
+
+        // The defenders are the next strongest: (2200 - 500) / 1.1
+        invade_planets(&mut u, &assaults);
+        assert_eq!((u.planets[0].owner_id, u.planets[0].population), (Some(1), 1545));
+        assert_eq!(message_ids(&u, 1), vec![MessageId::InvasionSucceeded as u16]);
+        assert_eq!(message_ids(&u, 2), vec![MessageId::InvasionTroopsDestroyed as u16]);
+    }
+
+    #[test]
+    fn colonization_races_go_to_the_larger_landing() {
+        let mut u = invasion_universe(2, 0);
+        u.planets[0].owner_id = None;
+        let location = u.planets[0].location.clone();
+
+        let design = ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Colony Ship".to_string(),
+            base_hull: TechnologyId::ColonyShip,
+            is_obsolete: false,
+            slots: Some(Default::default())
+        };
+
+        let mut fleet_ids = Vec::new();
+        for &(pid, colonists) in [(0, 30), (1, 10)].iter() {
+            let index = u.players[pid as usize].add_ship_design(design.clone());
+            let d = u.players[pid as usize].ship_designs[index as usize].clone().unwrap();
+            let fid = u.add_fleet(&d, Some(pid), location.clone(), 1);
+
+            let f = u.fleets.get_mut(&fid).unwrap();
+            f.cargo.colonists = colonists;
+            f.orders[0].task = ShipOrderType::Colonize;
+            fleet_ids.push(fid);
+        }
+
+        resolve_colonization_races(&mut u, &fleet_ids);
+
+        // Both land as attackers: (3300 - 1100) / 1.1 colonists survive
+        let winner = &u.fleets[&fleet_ids[0]];
+        assert_eq!(winner.cargo.colonists, 20);
+        assert!(matches!(winner.orders[0].task, ShipOrderType::Colonize));
+
+        let loser = &u.fleets[&fleet_ids[1]];
+        assert_eq!(loser.cargo.colonists, 0);
+        assert!(matches!(loser.orders[0].task, ShipOrderType::NoTask));
+
+        assert_eq!(message_ids(&u, 0), vec![MessageId::ColonizationRaceWon as u16]);
+        assert_eq!(message_ids(&u, 1), vec![MessageId::ColonizationRaceLost as u16]);
+    }
+}
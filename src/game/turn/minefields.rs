@@ -0,0 +1,506 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::Minefield;
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::fleet::ShipOrderType;
+use ::game::objects::tech::MineType;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::message::MessageId;
+use ::game::objects::player::PlayerRelation;
+
+// Fields lose this share of their mines each year, plus more for every
+// planet inside them, and vanish once too few mines remain
+pub const MINEFIELD_DECAY_PERCENT : f64 = 2.0;
+pub const MINEFIELD_DECAY_PERCENT_PER_PLANET : f64 = 4.0;
+pub const MINEFIELD_MAX_DECAY_PERCENT : f64 = 50.0;
+pub const MINIMUM_MINEFIELD_MINES : u32 = 10;
+
+/*
+    Fleets with a lay mines task at their current waypoint lay mines every
+    year.  Mines of each type are added to the owner's field of that type at
+    the same spot, or start a new field there.
+*/
+pub fn lay_mines(universe: &mut Universe) {
+    let mut fleet_ids : Vec<u32> = universe.fleets.iter()
+        .filter(|&(_, f)| match f.orders[0].task {
+            ShipOrderType::LayMines => f.location.is_same_location(&f.orders[0].location),
+            _ => false
+        })
+        .map(|(id, _)| *id)
+        .collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        lay_fleet_mines(universe, fid);
+    }
+}
+
+fn lay_fleet_mines(universe: &mut Universe, fleet_id: u32) {
+    let (owner_id, name, location) = match universe.fleets.get(&fleet_id) {
+        Some(f) => match f.owner_id {
+            Some(o) => (o, f.name.clone(), f.location.clone()),
+            None => return
+        },
+        None => return
+    };
+
+    let mut laid : Vec<(MineType, u32)> = Vec::new();
+    for m in universe.fleets[&fleet_id].members.iter() {
+        if let Some(d) = universe.lookup_ship_design(m.design_id) {
+            for s in d.filled_slots() {
+                let tech = &TECHNOLOGY_DETAILS[s.tid as usize];
+                if let (Some(per_year), Some(mine_type)) = (tech.mines_per_year, tech.mine_type) {
+                    let amount = per_year * s.amount as u32 * m.quantity as u32;
+                    match laid.iter_mut().find(|l| l.0 == mine_type) {
+                        Some(l) => l.1 += amount,
+                        None => laid.push((mine_type, amount))
+                    }
+                }
+            }
+        }
+    }
+
+    if laid.is_empty() {
+        universe.fleets.get_mut(&fleet_id).unwrap().orders[0].task = ShipOrderType::NoTask;
+        universe.players[owner_id as usize].add_message(MessageId::LayMinesNoDispensers, vec![name]);
+        return;
+    }
+
+    for (mine_type, amount) in laid {
+        let existing = universe.minefields.iter_mut()
+            .find(|mf| mf.owner_id == owner_id && mf.mine_type == mine_type && mf.location.is_same_location(&location));
+
+        match existing {
+            Some(mf) => {
+                mf.mines += amount;
+                universe.players[owner_id as usize].add_message(MessageId::MinefieldIncreased, vec![name.clone(), amount.to_string()]);
+            },
+            None => {
+                let id = universe.minefields.iter().map(|mf| mf.id + 1).max().unwrap_or(0);
+                universe.minefields.push(Minefield {
+                    id,
+                    location: location.clone(),
+                    owner_id,
+                    mine_type,
+                    mines: amount,
+                    detected_by: vec![owner_id]
+                });
+                universe.players[owner_id as usize].add_message(MessageId::MinesDispersed, vec![name.clone(), amount.to_string()]);
+            }
+        }
+    }
+}
+
+enum Sweeper {
+    Fleet(String),
+    Starbase(String)
+}
+
+/*
+    Armed fleets and starbases sweep every field they sit in, unless the
+    field belongs to a friend.  A sweeper clears its full sweep rate from
+    each field it is in.
+*/
+pub fn sweep_minefields(universe: &mut Universe) {
+    let mut sweepers : Vec<(u8, Sweeper, SpaceCoordinate, u32)> = Vec::new();
+
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+    for fid in fleet_ids {
+        let f = &universe.fleets[&fid];
+        if let Some(owner_id) = f.owner_id {
+            let rate = f.members.iter()
+                .filter_map(|m| universe.lookup_ship_design(m.design_id).map(|d| d.calculate_mine_sweep_rate() * m.quantity as u32))
+                .sum::<u32>();
+
+            if rate > 0 {
+                sweepers.push((owner_id, Sweeper::Fleet(f.name.clone()), f.location.clone(), rate));
+            }
+        }
+    }
+
+    let mut starbase_ids : Vec<u32> = universe.starbases.keys().cloned().collect();
+    starbase_ids.sort();
+    for sid in starbase_ids {
+        let sb = &universe.starbases[&sid];
+        let rate = universe.lookup_ship_design(sb.design_id).map_or(0, |d| d.calculate_mine_sweep_rate());
+
+        if let (Some(planet), true) = (universe.get_planet(sb.planet_id), rate > 0) {
+            sweepers.push((sb.owner_id, Sweeper::Starbase(planet.name.clone()), planet.location.clone(), rate));
+        }
+    }
+
+    for (owner_id, sweeper, location, rate) in sweepers {
+        for i in 0..universe.minefields.len() {
+            let (field_owner, mine_type, swept) = {
+                let mf = &universe.minefields[i];
+                if mf.owner_id == owner_id || mf.mines == 0 || !mf.contains(&location)
+                    || universe.players[owner_id as usize].relation_to(mf.owner_id) == PlayerRelation::Friend {
+                    continue;
+                }
+
+                (mf.owner_id, mf.type_name(), rate.min(mf.mines))
+            };
+
+            universe.minefields[i].mines -= swept;
+
+            let where_text = universe.describe_location(&universe.minefields[i].location);
+            let field_race = universe.players[field_owner as usize].race.name.clone();
+            let message = match sweeper {
+                Sweeper::Fleet(ref name) => (MessageId::FleetSweptMines, vec![name.clone(), swept.to_string(), field_race, mine_type.to_string(), where_text.clone()]),
+                Sweeper::Starbase(ref planet) => (MessageId::StarbaseSweptMines, vec![planet.clone(), swept.to_string(), field_race, mine_type.to_string(), where_text.clone()])
+            };
+
+            universe.players[owner_id as usize].add_message(message.0, message.1);
+            universe.players[field_owner as usize].add_message(MessageId::MinesSweptBySomeone, vec![swept.to_string(), mine_type.to_string(), where_text]);
+        }
+    }
+}
+
+pub fn decay_minefields(universe: &mut Universe) {
+    for mf in universe.minefields.iter_mut() {
+        let planets = universe.planets.iter().filter(|p| mf.contains(&p.location)).count() as f64;
+        let percent = (MINEFIELD_DECAY_PERCENT + MINEFIELD_DECAY_PERCENT_PER_PLANET * planets).min(MINEFIELD_MAX_DECAY_PERCENT);
+
+        let decay = ((mf.mines as f64) * percent / 100.0).ceil() as u32;
+        mf.mines = mf.mines.saturating_sub(decay);
+    }
+
+    universe.minefields.retain(|mf| mf.mines >= MINIMUM_MINEFIELD_MINES);
+}
+
+/*
+    A field is known to its owner, to anyone with a planet inside it, and to
+    anyone with a fleet whose scanners reach the edge of the field.
+*/
+pub fn update_minefield_detection(universe: &mut Universe) {
+    let mut scanners : Vec<(u8, SpaceCoordinate, f64)> = Vec::new();
+    for f in universe.fleets.values() {
+        if let Some(owner_id) = f.owner_id {
+            let range = f.members.iter()
+                .filter_map(|m| universe.lookup_ship_design(m.design_id))
+                .map(|d| d.scanner_range())
+                .max()
+                .unwrap_or(0);
+            scanners.push((owner_id, f.location.clone(), range as f64));
+        }
+    }
+
+    for p in universe.planets.iter() {
+        if let Some(owner_id) = p.owner_id {
+            scanners.push((owner_id, p.location.clone(), 0.0));
+        }
+    }
+
+    for mf in universe.minefields.iter_mut() {
+        let mut detected_by = vec![mf.owner_id];
+
+        for &(owner_id, ref location, range) in scanners.iter() {
+            if !detected_by.contains(&owner_id) && mf.location.distance_to(location) <= mf.radius() + range {
+                detected_by.push(owner_id);
+            }
+        }
+
+        mf.detected_by = detected_by;
+    }
+}
+
+/*
+    Distances along the path from start toward end at which the path is
+    inside the field, or None if it never enters it.
+*/
+fn path_through_field(start: &SpaceCoordinate, end: &SpaceCoordinate, field: &Minefield) -> Option<(f64, f64)> {
+    let length = start.distance_to(end);
+    if length == 0.0 {
+        return None;
+    }
+
+    let ux = (end.x as f64 - start.x as f64) / length;
+    let uy = (end.y as f64 - start.y as f64) / length;
+    let fx = start.x as f64 - field.location.x as f64;
+    let fy = start.y as f64 - field.location.y as f64;
+
+    let b = fx * ux + fy * uy;
+    let c = fx * fx + fy * fy - field.radius() * field.radius();
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let entry = (-b - discriminant.sqrt()).max(0.0);
+    let exit = (-b + discriminant.sqrt()).min(length);
+    if entry > exit {
+        return None;
+    }
+
+    Some((entry, exit))
+}
+
+/*
+    Checks a fleet's path for this year against every field it is not
+    safe in.  Fleets above a field's safe warp roll for a hit every light
+    year they spend inside it, with better odds of a hit the faster they
+    go; the first hit stops the fleet there and
+    damages it.  Returns where the fleet was stopped.
+*/
+pub fn check_minefield_collisions(universe: &mut Universe, rng: &mut StdRng, fleet_id: u32, start: &SpaceCoordinate, end: &SpaceCoordinate, warp: u8) -> Option<SpaceCoordinate> {
+    let owner_id = universe.fleets.get(&fleet_id)?.owner_id;
+
+    let mut hit : Option<(f64, usize)> = None;
+    for (i, mf) in universe.minefields.iter().enumerate() {
+        if Some(mf.owner_id) == owner_id || warp <= mf.safe_warp() {
+            continue;
+        }
+
+        if let Some((entry, exit)) = path_through_field(start, end, mf) {
+            let mut distance = entry.ceil();
+            while distance <= exit {
+                if rng.gen::<f64>() * 100.0 < mf.hit_chance_per_light_year(warp) {
+                    if hit.map_or(true, |(d, _)| distance < d) {
+                        hit = Some((distance, i));
+                    }
+                    break;
+                }
+                distance += 1.0;
+            }
+        }
+    }
+
+    let (distance, index) = hit?;
+    let stop = start.step_toward(end, distance);
+    apply_mine_hit(universe, fleet_id, index, &stop);
+    Some(stop)
+}
+
+fn apply_mine_hit(universe: &mut Universe, fleet_id: u32, minefield_index: usize, location: &SpaceCoordinate) {
+    let (fleet_owner, name, ships, ram_scoop) = {
+        let f = &universe.fleets[&fleet_id];
+        let ram_scoop = f.members.iter()
+            .filter_map(|m| universe.lookup_ship_design(m.design_id))
+            .any(|d| d.ram_scoop_free_warp().is_some());
+        (f.owner_id, f.name.clone(), f.ship_count(), ram_scoop)
+    };
+
+    let (field_owner, field_race, type_name, per_ship, minimum) = {
+        let mf = &universe.minefields[minefield_index];
+        let (per_ship, minimum) = mf.damage(ram_scoop);
+        (mf.owner_id, universe.players[mf.owner_id as usize].race.name.clone(), mf.type_name(), per_ship, minimum)
+    };
+
+    let damage = if per_ship == 0 { 0 } else { (per_ship * ships).max(minimum) };
+    let destroyed = universe.apply_fleet_damage(fleet_id, damage);
+    let annihilated = !universe.fleets.contains_key(&fleet_id);
+    let where_text = universe.describe_location(location);
+
+    let (victim_message, owner_message) = if annihilated {
+        ((MessageId::MinefieldAnnihilated, vec![name.clone(), field_race, type_name.to_string(), where_text.clone()]),
+         (MessageId::OwnMinefieldAnnihilated, vec![name, type_name.to_string(), where_text]))
+    }
+    else if damage == 0 {
+        ((MessageId::MinefieldStopped, vec![name.clone(), field_race, type_name.to_string(), where_text.clone()]),
+         (MessageId::OwnMinefieldStopped, vec![name, type_name.to_string(), where_text]))
+    }
+    else if destroyed == 0 {
+        ((MessageId::MinefieldDamaged, vec![name.clone(), field_race, type_name.to_string(), where_text.clone(), damage.to_string()]),
+         (MessageId::OwnMinefieldDamaged, vec![name, type_name.to_string(), where_text, damage.to_string()]))
+    }
+    else {
+        ((MessageId::MinefieldShipsDestroyed, vec![name.clone(), field_race, type_name.to_string(), where_text.clone(), damage.to_string(), destroyed.to_string()]),
+         (MessageId::OwnMinefieldShipsDestroyed, vec![name, type_name.to_string(), where_text, damage.to_string(), destroyed.to_string()]))
+    };
+
+    if let Some(pid) = fleet_owner {
+        universe.players[pid as usize].add_message(victim_message.0, victim_message.1);
+    }
+    universe.players[field_owner as usize].add_message(owner_message.0, owner_message.1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use ::game::objects::player::Player;
+    use ::game::objects::fleet::ShipDesign;
+    use ::game::objects::fleet::ShipSlot;
+    use ::game::objects::tech::TechnologyId;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::tutorial::generate_tutorial_universe;
+
+    const FIELD : SpaceCoordinate = SpaceCoordinate { x: 1000, y: 1000 };
+
+    // The tutorial map with two Humanoid players at war
+    fn two_player_universe() -> Universe {
+        let mut u = generate_tutorial_universe();
+        for pid in 0..2 {
+            let mut player = Player::construct_from_race(create_humanoid());
+            player.id = pid;
+            u.players.push(player);
+        }
+        u
+    }
+
+    fn add_fleet(u: &mut Universe, owner_id: u8, hull: TechnologyId, parts: &[(usize, TechnologyId, u8)], location: SpaceCoordinate, quantity: u16) -> u32 {
+        let mut slots : [Option<ShipSlot>; 16] = Default::default();
+        for &(slot, tid, amount) in parts.iter() {
+            slots[slot] = Some(ShipSlot { tid, amount });
+        }
+
+        let player = &mut u.players[owner_id as usize];
+        let index = player.add_ship_design(ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Test".to_string(),
+            base_hull: hull,
+            is_obsolete: false,
+            slots: Some(slots)
+        });
+        let design = player.ship_designs[index as usize].clone().unwrap();
+        u.add_fleet(&design, Some(owner_id), location, quantity)
+    }
+
+    fn add_field(u: &mut Universe, owner_id: u8, mine_type: MineType, mines: u32) {
+        let id = u.minefields.len() as u32;
+        u.minefields.push(Minefield { id, location: FIELD, owner_id, mine_type, mines, detected_by: vec![owner_id] });
+    }
+
+    fn message_ids(u: &Universe, player_id: u8) -> Vec<u16> {
+        u.players[player_id as usize].messages.iter().map(|m| m.message_id).collect()
+    }
+
+    #[test]
+    fn hit_chance_grows_with_each_warp_above_the_safe_warp() {
+        let mf = Minefield { id: 0, location: FIELD, owner_id: 0, mine_type: MineType::Normal, mines: 100, detected_by: Vec::new() };
+
+        assert_eq!(mf.hit_chance_per_light_year(4), 0.0);
+        assert!((mf.hit_chance_per_light_year(5) - 0.3).abs() < 1e-9);
+        assert!((mf.hit_chance_per_light_year(9) - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fleets_at_the_safe_warp_or_in_their_own_fields_pass_through() {
+        let mut u = two_player_universe();
+        let mut rng = StdRng::seed_from_u64(1);
+        add_field(&mut u, 0, MineType::Speed, 40000);
+        let end = SpaceCoordinate { x: FIELD.x + 100, y: FIELD.y };
+
+        let own = add_fleet(&mut u, 0, TechnologyId::Scout, &[], FIELD, 1);
+        assert!(check_minefield_collisions(&mut u, &mut rng, own, &FIELD, &end, 10).is_none());
+
+        let other = add_fleet(&mut u, 1, TechnologyId::Scout, &[], FIELD, 1);
+        assert!(check_minefield_collisions(&mut u, &mut rng, other, &FIELD, &end, 5).is_none());
+        assert!(message_ids(&u, 1).is_empty());
+    }
+
+    #[test]
+    fn speed_bumps_stop_fleets_without_damage() {
+        let mut u = two_player_universe();
+        add_field(&mut u, 0, MineType::Speed, 40000);
+        let fid = add_fleet(&mut u, 1, TechnologyId::Scout, &[], FIELD, 1);
+        let end = SpaceCoordinate { x: FIELD.x + 100, y: FIELD.y };
+
+        let stop = check_minefield_collisions(&mut u, &mut StdRng::seed_from_u64(1), fid, &FIELD, &end, 10).unwrap();
+        assert!(stop.x < end.x);
+        assert!(u.minefields[0].contains(&stop));
+        assert_eq!(u.fleets[&fid].members[0].damage, 0);
+        assert_eq!(message_ids(&u, 1), vec![MessageId::MinefieldStopped as u16]);
+        assert_eq!(message_ids(&u, 0), vec![MessageId::OwnMinefieldStopped as u16]);
+    }
+
+    #[test]
+    fn mine_hits_deal_at_least_the_fields_minimum_damage() {
+        let mut u = two_player_universe();
+        add_field(&mut u, 0, MineType::Normal, 40000);
+        let fid = add_fleet(&mut u, 1, TechnologyId::Scout, &[], FIELD, 1);
+        let end = SpaceCoordinate { x: FIELD.x + 200, y: FIELD.y };
+
+        // One scout takes the 500 minimum rather than 100 per ship
+        assert!(check_minefield_collisions(&mut u, &mut StdRng::seed_from_u64(1), fid, &FIELD, &end, 10).is_some());
+        assert!(!u.fleets.contains_key(&fid));
+        assert_eq!(message_ids(&u, 1), vec![MessageId::MinefieldAnnihilated as u16]);
+        assert_eq!(message_ids(&u, 0), vec![MessageId::OwnMinefieldAnnihilated as u16]);
+    }
+
+    #[test]
+    fn mine_layers_start_a_field_and_then_add_to_it() {
+        let mut u = two_player_universe();
+        let fid = add_fleet(&mut u, 0, TechnologyId::MiniMineLayer, &[(1, TechnologyId::MineDispenser40, 2)], FIELD, 1);
+        u.fleets.get_mut(&fid).unwrap().orders[0].task = ShipOrderType::LayMines;
+
+        lay_mines(&mut u);
+        lay_mines(&mut u);
+
+        assert_eq!(u.minefields.len(), 1);
+        assert_eq!((u.minefields[0].owner_id, u.minefields[0].mines), (0, 160));
+        assert_eq!(message_ids(&u, 0), vec![MessageId::MinesDispersed as u16, MessageId::MinefieldIncreased as u16]);
+    }
+
+    #[test]
+    fn fields_decay_and_vanish_below_the_minimum() {
+        let mut u = two_player_universe();
+        add_field(&mut u, 0, MineType::Normal, 1000);
+        u.minefields.push(Minefield { id: 1, location: SpaceCoordinate { x: 3000, y: 3000 }, owner_id: 0, mine_type: MineType::Normal, mines: MINIMUM_MINEFIELD_MINES, detected_by: Vec::new() });
+
+        decay_minefields(&mut u);
+        assert_eq!(u.minefields.len(), 1);
+        assert_eq!(u.minefields[0].mines, 980);
+    }
+
+    #[test]
+    fn armed_fleets_sweep_enemy_fields_they_sit_in() {
+        let mut u = two_player_universe();
+        add_field(&mut u, 0, MineType::Normal, 1000);
+        add_fleet(&mut u, 1, TechnologyId::Destroyer, &[(1, TechnologyId::Laser, 1)], FIELD, 2);
+
+        // A laser sweeps power * range^2 = 10 mines per ship
+        sweep_minefields(&mut u);
+        assert_eq!(u.minefields[0].mines, 980);
+        assert_eq!(message_ids(&u, 1), vec![MessageId::FleetSweptMines as u16]);
+        assert_eq!(message_ids(&u, 0), vec![MessageId::MinesSweptBySomeone as u16]);
+    }
+
+    #[test]
+    fn fleets_leave_their_own_and_friendly_fields_alone() {
+        let mut u = two_player_universe();
+        add_field(&mut u, 0, MineType::Normal, 1000);
+        add_fleet(&mut u, 0, TechnologyId::Destroyer, &[(1, TechnologyId::Laser, 1)], FIELD, 1);
+        add_fleet(&mut u, 1, TechnologyId::Destroyer, &[(1, TechnologyId::Laser, 1)], FIELD, 1);
+        u.players[1].set_relation(0, PlayerRelation::Friend);
+
+        sweep_minefields(&mut u);
+        assert_eq!(u.minefields[0].mines, 1000);
+    }
+
+    #[test]
+    fn unarmed_fleets_and_fleets_outside_the_field_do_not_sweep() {
+        let mut u = two_player_universe();
+        add_field(&mut u, 0, MineType::Normal, 100);
+        add_fleet(&mut u, 1, TechnologyId::Scout, &[], FIELD, 1);
+        add_fleet(&mut u, 1, TechnologyId::Destroyer, &[(1, TechnologyId::Laser, 1)], SpaceCoordinate { x: FIELD.x + 11, y: FIELD.y }, 1);
+
+        sweep_minefields(&mut u);
+        assert_eq!(u.minefields[0].mines, 100);
+    }
+}
@@ -0,0 +1,114 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::ShipOrderType;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::message::MessageId;
+
+/*
+    Total mining value of a fleet's robots.  Only robots the owner's race
+    can use count, so Only Basic Remote Mining races get nothing from the
+    advanced robots and only Advanced Remote Mining races get the benefit
+    of the robots that trait unlocks.
+*/
+pub fn calculate_mining_value(universe: &Universe, fleet_id: u32) -> u32 {
+    let f = &universe.fleets[&fleet_id];
+    let owner = match f.owner_id {
+        Some(o) => &universe.players[o as usize],
+        None => return 0
+    };
+
+    let mut value = 0;
+    for m in f.members.iter() {
+        if let Some(d) = universe.lookup_ship_design(m.design_id) {
+            for s in d.filled_slots() {
+                if let Some(mining) = TECHNOLOGY_DETAILS[s.tid as usize].mining_value {
+                    if owner.available_tech_ids.contains(&s.tid) {
+                        value += mining as u32 * s.amount as u32 * m.quantity as u32;
+                    }
+                }
+            }
+        }
+    }
+
+    value
+}
+
+/*
+    Runs every fleet with a remote mining task at its current waypoint.
+    Remote mining continues each year until the order is changed; it is
+    canceled if the fleet has no usable robots, is in deep space, or the
+    planet becomes inhabited.
+*/
+pub fn remote_mine_planets(universe: &mut Universe) {
+    let mut fleet_ids : Vec<u32> = universe.fleets.iter()
+        .filter(|&(_, f)| match f.orders[0].task {
+            ShipOrderType::RemoteMining => f.location.is_same_location(&f.orders[0].location),
+            _ => false
+        })
+        .map(|(id, _)| *id)
+        .collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        remote_mine(universe, fid);
+    }
+}
+
+pub fn remote_mine(universe: &mut Universe, fleet_id: u32) {
+    let (owner_id, name, location) = match universe.fleets.get(&fleet_id) {
+        Some(f) => match f.owner_id {
+            Some(o) => (o, f.name.clone(), f.location.clone()),
+            None => return
+        },
+        None => return
+    };
+
+    let planet_index = universe.planets.iter().position(|p| p.location.is_same_location(&location));
+    let mining_value = calculate_mining_value(universe, fleet_id);
+
+    let cancel = match planet_index {
+        None => Some((MessageId::RemoteMiningDeepSpace, vec![name])),
+        Some(i) => {
+            let planet_name = universe.planets[i].name.clone();
+            if mining_value == 0 {
+                Some((MessageId::RemoteMiningNoModules, vec![name, planet_name]))
+            }
+            else if universe.planets[i].owner_id.is_some() || universe.planets[i].population > 0 {
+                Some((MessageId::RemoteMiningInhabited, vec![name, planet_name]))
+            }
+            else {
+                None
+            }
+        }
+    };
+
+    match cancel {
+        Some((message_id, parameters)) => {
+            universe.fleets.get_mut(&fleet_id).unwrap().orders[0].task = ShipOrderType::NoTask;
+            universe.players[owner_id as usize].add_message(message_id, parameters);
+        },
+        None => {
+            universe.planets[planet_index.unwrap()].extract_minerals(mining_value);
+        }
+    }
+}
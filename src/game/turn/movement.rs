@@ -0,0 +1,443 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::rngs::StdRng;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::fleet::Fleet;
+use ::game::objects::fleet::FleetMember;
+use ::game::objects::fleet::WaypointTarget;
+use ::game::objects::tech::TECHNOLOGY_DETAILS;
+use ::game::objects::race::LesserRacialTrait;
+use ::game::objects::message::MessageId;
+use ::game::turn::minefields::check_minefield_collisions;
+use ::game::turn::stargates::use_stargate;
+
+// Warp speed every engine can run at without burning fuel
+pub const FREE_WARP : u8 = 1;
+
+// Waypoint warp that sends a fleet through the stargate it orbits
+pub const STARGATE_WARP : u8 = 11;
+
+pub const FUEL_USAGE_DIVISOR : f64 = 2000.0;
+pub const IMPROVED_FUEL_EFFICIENCY_FACTOR : f64 = 0.85;
+
+// Light years a ram scoop ship must travel to collect 1mg of fuel
+pub const RAM_SCOOP_LIGHT_YEARS_PER_MG : f64 = 5.0;
+
+pub fn warp_distance(warp: u8) -> f64 {
+    (warp as f64) * (warp as f64)
+}
+
+/*
+    Fuel burned (in mg) by the given ships for a trip.  Each design burns
+    fuel at its engine's efficiency for the warp, scaled by the design's
+    mass:
+
+        fuel = mass * efficiency[warp] * distance / 2000
+
+    Cargo is spread across the ships in proportion to their cargo space.
+    Improved Fuel Efficiency races burn 15% less.  None means some ship in
+    the fleet has no engine and the fleet cannot move.
+*/
+pub fn calculate_fuel_usage(universe: &Universe, members: &[FleetMember], cargo_mass: u32, warp: u8, distance: f64, improved_fuel_efficiency: bool) -> Option<u32> {
+    let total_cargo_capacity = Fleet::calculate_total_cargo_capacity(universe, members);
+    let mut usage = 0.0;
+
+    for member in members.iter() {
+        let design = universe.lookup_ship_design(member.design_id)?;
+        let table = TECHNOLOGY_DETAILS[design.engine()? as usize].fuel_table?;
+        let efficiency = table[warp.min(10) as usize] as f64;
+        let mut mass = (design.calculate_mass() * member.quantity as u32) as f64;
+
+        if total_cargo_capacity > 0 {
+            let share = (design.calculate_cargo_capacity() * member.quantity as u32) as f64 / total_cargo_capacity as f64;
+            mass += cargo_mass as f64 * share;
+        }
+
+        usage += mass * efficiency * distance / FUEL_USAGE_DIVISOR;
+    }
+
+    if improved_fuel_efficiency {
+        usage *= IMPROVED_FUEL_EFFICIENCY_FACTOR;
+    }
+
+    Some(usage.ceil() as u32)
+}
+
+/*
+    Ram scoop ships travelling at or below their free speed collect
+    interstellar hydrogen along the way.
+*/
+pub fn calculate_ram_scoop_fuel(universe: &Universe, members: &[FleetMember], warp: u8, distance: f64) -> u32 {
+    if warp <= FREE_WARP {
+        return 0;
+    }
+
+    let mut scooping_ships = 0;
+    for member in members.iter() {
+        if let Some(design) = universe.lookup_ship_design(member.design_id) {
+            match design.ram_scoop_free_warp() {
+                Some(free_warp) if warp <= free_warp => scooping_ships += member.quantity as u32,
+                _ => {}
+            }
+        }
+    }
+
+    ((scooping_ships as f64) * distance / RAM_SCOOP_LIGHT_YEARS_PER_MG).floor() as u32
+}
+
+/*
+    Moves every fleet and returns the ids of the fleets that reached a
+    waypoint this year, in id order.  Stalled fleets stay where they are.
+*/
+pub fn move_fleets(universe: &mut Universe, rng: &mut StdRng, stalled: &[u32]) -> Vec<u32> {
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids.iter() {
+        update_waypoint_targets(universe, *fid);
+    }
+
+    fleet_ids.into_iter()
+        .filter(|fid| !stalled.contains(fid) && move_fleet(universe, rng, *fid))
+        .collect()
+}
+
+/*
+    Waypoints that follow a fleet or wormhole move with their target.  When
+    the target is destroyed or can no longer be seen, the waypoint stays at
+    the target's last known location; if that location is a planet, the
+    waypoint targets the planet instead.
+*/
+pub fn update_waypoint_targets(universe: &mut Universe, fleet_id: u32) {
+    let (owner_id, name, orders) = match universe.fleets.get(&fleet_id) {
+        Some(f) => (f.owner_id, f.name.clone(), f.orders.clone()),
+        None => return
+    };
+
+    let mut updated = orders.clone();
+    let mut messages = Vec::new();
+
+    for (i, w) in orders.iter().enumerate().skip(1) {
+        match w.target {
+            WaypointTarget::Fleet(tid) => {
+                let visible = match owner_id {
+                    Some(pid) => universe.is_fleet_visible_to(pid, tid),
+                    None => true
+                };
+
+                if visible {
+                    updated[i].location = universe.fleets[&tid].location.clone();
+                }
+                else if !universe.fleets.contains_key(&tid) {
+                    updated[i].target = WaypointTarget::Space;
+                    messages.push((MessageId::WaypointTargetDestroyed, vec![name.clone(), format!("Fleet #{}", tid)]));
+                }
+                else {
+                    match universe.planets.iter().find(|p| p.location.is_same_location(&w.location)) {
+                        Some(p) => {
+                            updated[i].target = WaypointTarget::Planet(p.id);
+                            messages.push((MessageId::WaypointTargetBehindPlanet, vec![name.clone(), p.name.clone()]));
+                        },
+                        None => {
+                            updated[i].target = WaypointTarget::Space;
+                            messages.push((MessageId::WaypointTargetOutOfRange, vec![name.clone()]));
+                        }
+                    }
+                }
+            },
+            WaypointTarget::Wormhole(wid) => {
+                match universe.wormholes.iter().find(|wh| wh.id == wid) {
+                    Some(wh) => updated[i].location = wh.location.clone(),
+                    None => {
+                        updated[i].target = WaypointTarget::Space;
+                        messages.push((MessageId::WaypointWormholeVanished, vec![name.clone()]));
+                    }
+                }
+            },
+            WaypointTarget::MysteryTrader(tid) => {
+                match universe.mystery_traders.iter().find(|t| t.id == tid) {
+                    Some(t) => updated[i].location = t.location.clone(),
+                    None => {
+                        updated[i].target = WaypointTarget::Space;
+                        messages.push((MessageId::WaypointMysteryTraderVanished, vec![name.clone()]));
+                    }
+                }
+            },
+            WaypointTarget::Planet(_) | WaypointTarget::Space => {}
+        }
+    }
+
+    universe.fleets.get_mut(&fleet_id).unwrap().orders = updated;
+
+    if let Some(pid) = owner_id {
+        let player = &mut universe.players[pid as usize];
+        for (message_id, parameters) in messages {
+            player.add_message(message_id, parameters);
+        }
+    }
+}
+
+/*
+    Advances a fleet one year toward its next waypoint.
+
+    A fleet covers warp^2 light years per year.  If it cannot afford the
+    fuel for the whole leg, it travels as far as the fuel allows at the
+    ordered speed and spends the rest of the year at warp 1, which is free
+    for every engine.  Fleets waiting to finish repairs stay where they
+    are.  A fleet that hits a mine is stopped where it hit.
+    Fleets that arrive at a planet enter its orbit.  Fleets ordered to
+    their waypoint at STARGATE_WARP jump there instead.  Returns whether
+    the fleet reached its waypoint.
+*/
+pub fn move_fleet(universe: &mut Universe, rng: &mut StdRng, fleet_id: u32) -> bool {
+    match universe.fleets.get(&fleet_id).and_then(|f| f.next_waypoint()) {
+        Some(w) if w.warp == STARGATE_WARP => return use_stargate(universe, rng, fleet_id),
+        _ => {}
+    }
+
+    let (start, destination, mut warp, owner_id, current_fuel, fuel_capacity, cargo_mass, name) = match universe.fleets.get(&fleet_id) {
+        Some(f) => match f.next_waypoint() {
+            Some(w) if w.warp > 0 && !f.is_holding_for_repair() => {
+                (f.location.clone(), w.location.clone(), w.warp, f.owner_id, f.current_fuel, f.total_fuel_capacity, f.cargo.total(), f.name.clone())
+            },
+            _ => return false
+        },
+        None => return false
+    };
+
+    let improved_fuel_efficiency = match owner_id {
+        Some(pid) => universe.players[pid as usize].race.lesser_racial_traits.contains(&LesserRacialTrait::ImprovedFuelEfficiency),
+        None => false
+    };
+
+    let total_distance = start.distance_to(&destination);
+    let mut messages = Vec::new();
+
+    let members = &universe.fleets[&fleet_id].members;
+    let usage_per_ly = match calculate_fuel_usage(universe, members, cargo_mass, warp, 1.0, improved_fuel_efficiency) {
+        Some(u) => u,
+        None => {
+            info!("Fleet {} has a ship without an engine and cannot move", fleet_id);
+            return false;
+        }
+    };
+
+    if current_fuel == 0 && usage_per_ly > 0 {
+        warp = FREE_WARP;
+        messages.push((MessageId::FleetOutOfFuelWarpDecreased, vec![name.clone(), FREE_WARP.to_string()]));
+    }
+
+    let mut travelled = total_distance.min(warp_distance(warp));
+    let needed = calculate_fuel_usage(universe, members, cargo_mass, warp, travelled, improved_fuel_efficiency).unwrap_or(0);
+    let mut fuel = current_fuel;
+
+    if needed > fuel {
+        let at_speed = travelled * (fuel as f64) / (needed as f64);
+        let remaining_year = 1.0 - at_speed / warp_distance(warp);
+        travelled = total_distance.min(at_speed + remaining_year * warp_distance(FREE_WARP));
+
+        fuel = 0;
+        warp = FREE_WARP;
+        messages.push((MessageId::FleetOutOfFuelWarpDecreased, vec![name.clone(), FREE_WARP.to_string()]));
+    }
+    else {
+        fuel -= needed;
+
+        if fuel == 0 && needed > 0 {
+            messages.push((MessageId::FleetOutOfFuel, vec![name.clone()]));
+        }
+
+        if needed == 0 {
+            let scooped = calculate_ram_scoop_fuel(universe, members, warp, travelled).min(fuel_capacity - fuel);
+            if scooped > 0 {
+                fuel += scooped;
+                messages.push((MessageId::RamScoopFuelProduced, vec![name.clone(), scooped.to_string()]));
+            }
+        }
+    }
+
+    if let Some(pid) = owner_id {
+        let player = &mut universe.players[pid as usize];
+        for (message_id, parameters) in messages {
+            player.add_message(message_id, parameters);
+        }
+    }
+
+    let mut arrived = travelled >= total_distance;
+    let mut location = start.step_toward(&destination, travelled);
+
+    if let Some(stop) = check_minefield_collisions(universe, rng, fleet_id, &start, &location, warp) {
+        if !universe.fleets.contains_key(&fleet_id) {
+            return false;
+        }
+
+        arrived = false;
+        location = stop;
+    }
+
+    update_orbits(universe, fleet_id, &start, &location);
+
+    let f = universe.fleets.get_mut(&fleet_id).unwrap();
+    f.location = location;
+    f.current_fuel = fuel;
+    f.orders[1].warp = warp;
+    if arrived {
+        f.advance_waypoint();
+    }
+
+    arrived
+}
+
+pub fn update_orbits(universe: &mut Universe, fleet_id: u32, from: &SpaceCoordinate, to: &SpaceCoordinate) {
+    if from.is_same_location(to) {
+        return;
+    }
+
+    for p in universe.planets.iter_mut() {
+        if p.location.is_same_location(from) {
+            p.related_fleets.retain(|id| *id != fleet_id);
+        }
+        else if p.location.is_same_location(to) && !p.related_fleets.contains(&fleet_id) {
+            p.related_fleets.push(fleet_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use ::game::objects::player::Player;
+    use ::game::objects::fleet::ShipDesign;
+    use ::game::objects::fleet::ShipSlot;
+    use ::game::objects::fleet::Waypoint;
+    use ::game::objects::fleet::ShipOrderType;
+    use ::game::objects::tech::TechnologyId;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::tutorial::generate_tutorial_universe;
+
+    const START : SpaceCoordinate = SpaceCoordinate { x: 1000, y: 1000 };
+
+    // A lone scout with the given engine, ordered 100ly east at the given warp
+    fn scout_universe(engine: TechnologyId, warp: u8) -> (Universe, u32) {
+        let mut u = generate_tutorial_universe();
+        let mut player = Player::construct_from_race(create_humanoid());
+        player.id = 0;
+        u.players.push(player);
+
+        let mut slots : [Option<ShipSlot>; 16] = Default::default();
+        slots[0] = Some(ShipSlot { tid: engine, amount: 1 });
+        let index = u.players[0].add_ship_design(ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Scout".to_string(),
+            base_hull: TechnologyId::Scout,
+            is_obsolete: false,
+            slots: Some(slots)
+        });
+        let design = u.players[0].ship_designs[index as usize].clone().unwrap();
+
+        let fid = u.add_fleet(&design, Some(0), START, 1);
+        let destination = SpaceCoordinate { x: START.x + 100, y: START.y };
+        u.fleets.get_mut(&fid).unwrap().orders.push(Waypoint::construct(WaypointTarget::Space, destination, warp, ShipOrderType::NoTask));
+        (u, fid)
+    }
+
+    fn mass_of(u: &Universe, fid: u32) -> f64 {
+        u.lookup_ship_design(u.fleets[&fid].members[0].design_id).unwrap().calculate_mass() as f64
+    }
+
+    fn last_message(u: &Universe) -> Option<u16> {
+        u.players[0].messages.last().map(|m| m.message_id)
+    }
+
+    #[test]
+    fn fleets_burn_fuel_for_the_distance_travelled() {
+        let (mut u, fid) = scout_universe(TechnologyId::QuickJump5, 5);
+        let fuel = u.fleets[&fid].current_fuel;
+
+        // QuickJump 5 burns 100 per 2000kg-ly at warp 5
+        let expected = (mass_of(&u, fid) * 100.0 * 25.0 / FUEL_USAGE_DIVISOR).ceil() as u32;
+        assert!(expected > 0);
+
+        assert!(!move_fleet(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        assert_eq!(u.fleets[&fid].location.x, START.x + 25);
+        assert_eq!(u.fleets[&fid].current_fuel, fuel - expected);
+    }
+
+    #[test]
+    fn improved_fuel_efficiency_burns_less_fuel() {
+        let (u, fid) = scout_universe(TechnologyId::QuickJump5, 5);
+        let members = &u.fleets[&fid].members;
+
+        let normal = calculate_fuel_usage(&u, members, 0, 5, 1000.0, false).unwrap();
+        let improved = calculate_fuel_usage(&u, members, 0, 5, 1000.0, true).unwrap();
+        assert_eq!(improved, (normal as f64 * IMPROVED_FUEL_EFFICIENCY_FACTOR).ceil() as u32);
+    }
+
+    #[test]
+    fn fleets_without_fuel_drop_to_warp_one() {
+        let (mut u, fid) = scout_universe(TechnologyId::QuickJump5, 5);
+        u.fleets.get_mut(&fid).unwrap().current_fuel = 0;
+
+        move_fleet(&mut u, &mut StdRng::seed_from_u64(1), fid);
+        assert_eq!(u.fleets[&fid].location.x, START.x + 1);
+        assert_eq!(u.fleets[&fid].orders[1].warp, FREE_WARP);
+        assert_eq!(last_message(&u), Some(MessageId::FleetOutOfFuelWarpDecreased as u16));
+    }
+
+    #[test]
+    fn fleets_that_run_dry_finish_the_year_at_warp_one() {
+        let (mut u, fid) = scout_universe(TechnologyId::QuickJump5, 5);
+        let needed = (mass_of(&u, fid) * 100.0 * 25.0 / FUEL_USAGE_DIVISOR).ceil() as u32;
+        u.fleets.get_mut(&fid).unwrap().current_fuel = needed / 2;
+
+        move_fleet(&mut u, &mut StdRng::seed_from_u64(1), fid);
+        let travelled = u.fleets[&fid].location.x - START.x;
+        assert!(travelled > 1 && travelled < 25);
+        assert_eq!(u.fleets[&fid].current_fuel, 0);
+        assert_eq!(last_message(&u), Some(MessageId::FleetOutOfFuelWarpDecreased as u16));
+    }
+
+    #[test]
+    fn ram_scoops_collect_fuel_at_their_free_speed() {
+        let (mut u, fid) = scout_universe(TechnologyId::SettlersDelight, 6);
+        u.fleets.get_mut(&fid).unwrap().current_fuel = 0;
+
+        move_fleet(&mut u, &mut StdRng::seed_from_u64(1), fid);
+        assert_eq!(u.fleets[&fid].location.x, START.x + 36);
+        assert_eq!(u.fleets[&fid].current_fuel, (36.0 / RAM_SCOOP_LIGHT_YEARS_PER_MG) as u32);
+        assert_eq!(last_message(&u), Some(MessageId::RamScoopFuelProduced as u16));
+    }
+
+    #[test]
+    fn ram_scoops_burn_fuel_above_their_free_speed() {
+        let (u, fid) = scout_universe(TechnologyId::SettlersDelight, 7);
+        let members = &u.fleets[&fid].members;
+
+        assert_eq!(calculate_ram_scoop_fuel(&u, members, 6, 36.0), 7);
+        assert_eq!(calculate_ram_scoop_fuel(&u, members, 7, 49.0), 0);
+        assert!(calculate_fuel_usage(&u, members, 0, 7, 49.0, false).unwrap() > 0);
+    }
+}
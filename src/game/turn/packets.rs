@@ -0,0 +1,141 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::MineralPacket;
+use ::game::objects::message::MessageId;
+use ::game::turn::movement::warp_distance;
+
+// Divides the packet's excess speed times its mass to give its damage
+pub const PACKET_DAMAGE_DIVISOR : f64 = 160.0;
+
+// Share of the minerals the receiver fails to catch that survive impact
+pub const PACKET_UNCAUGHT_RECOVERY : f64 = 1.0 / 3.0;
+
+pub const PACKET_MIN_COLONISTS_KILLED_PER_DAMAGE : f64 = 100.0;
+pub const PACKET_MIN_DEFENSES_DESTROYED_PER_DAMAGE : f64 = 0.05;
+
+/*
+    Moves every mineral packet warp^2 light years toward its destination.
+    Packets that arrive this year hit their destination and are removed.
+*/
+pub fn move_packets(universe: &mut Universe) {
+    let mut arrived = Vec::new();
+
+    for mp in universe.mineral_packets.iter_mut() {
+        let step = warp_distance(mp.heading.warp);
+        if mp.location.distance_to(&mp.heading.next_location) <= step {
+            mp.location = mp.heading.next_location.clone();
+            arrived.push(mp.id);
+        }
+        else {
+            mp.location = mp.location.step_toward(&mp.heading.next_location, step);
+        }
+    }
+
+    for id in arrived {
+        let index = universe.mineral_packets.iter().position(|mp| mp.id == id).unwrap();
+        let packet = universe.mineral_packets.remove(index);
+        catch_packet(universe, &packet);
+    }
+}
+
+/*
+    A packet is caught safely when the destination's mass driver is rated
+    at least as fast as the packet.  Otherwise the driver only slows it in
+    proportion to the square of its rating, and the rest slams into the
+    planet:
+
+        caught  = driver^2 / packet^2
+        damage  = (packet^2 - driver^2) * mass / 160 * (1 - coverage)
+
+    Everything caught lands on the surface along with a third of what was
+    not.  Each point of damage kills the larger of 0.1% of the colonists
+    or 100 colonists, and the larger of 0.1% of the defenses or one defense
+    for every 20 points.
+*/
+fn catch_packet(universe: &mut Universe, packet: &MineralPacket) {
+    let speed = warp_distance(packet.heading.warp);
+    let catch = warp_distance(universe.planet_mass_driver_warp(packet.destination_id).unwrap_or(0));
+    let mass = packet.minerals.total();
+
+    let p = match universe.get_planet_mut(packet.destination_id) {
+        Some(p) => p,
+        None => return
+    };
+
+    let caught = (catch / speed).min(1.0);
+    let recovered = caught + (1.0 - caught) * PACKET_UNCAUGHT_RECOVERY;
+    p.on_surface.add_saturating(
+        (packet.minerals.ironium as f64 * recovered) as u32,
+        (packet.minerals.boranium as f64 * recovered) as u32,
+        (packet.minerals.germanium as f64 * recovered) as u32);
+
+    let planet_name = p.name.clone();
+    let owner_id = match p.owner_id {
+        Some(o) => o,
+        None => {
+            if caught < 1.0 {
+                universe.players[packet.owner_id as usize].add_message(MessageId::PacketHitUninhabited, vec![planet_name, mass.to_string()]);
+            }
+            return;
+        }
+    };
+
+    if caught >= 1.0 {
+        universe.players[owner_id as usize].add_message(MessageId::PacketCaught, vec![planet_name, mass.to_string()]);
+        return;
+    }
+
+    let damage = (speed - catch) * mass as f64 / PACKET_DAMAGE_DIVISOR * (1.0 - p.defense_coverage());
+    let killed = (p.population as f64 * damage / 1000.0).max(damage * PACKET_MIN_COLONISTS_KILLED_PER_DAMAGE).min(p.population as f64) as u32;
+    let destroyed = (p.defenses as f64 * damage / 1000.0).max(damage * PACKET_MIN_DEFENSES_DESTROYED_PER_DAMAGE).min(p.defenses as f64) as u16;
+
+    p.population -= killed;
+    p.defenses -= destroyed;
+
+    let wiped_out = p.population == 0;
+    if wiped_out {
+        p.owner_id = None;
+    }
+
+    let (mass_text, killed_text, destroyed_text) = (mass.to_string(), killed.to_string(), destroyed.to_string());
+    let message = if wiped_out {
+        (MessageId::PacketAnnihilated, vec![planet_name])
+    }
+    else if killed == 0 && destroyed == 0 {
+        (MessageId::PacketBombardedNoDamage, vec![planet_name, mass_text])
+    }
+    else if catch > 0.0 && destroyed > 0 {
+        (MessageId::PacketPartiallyCaughtDefensesLost, vec![planet_name, mass_text, killed_text, destroyed_text])
+    }
+    else if catch > 0.0 {
+        (MessageId::PacketPartiallyCaught, vec![planet_name, mass_text, killed_text])
+    }
+    else if destroyed > 0 {
+        (MessageId::PacketBombardedDefensesLost, vec![planet_name, mass_text, killed_text, destroyed_text])
+    }
+    else {
+        (MessageId::PacketBombarded, vec![planet_name, mass_text, killed_text])
+    };
+
+    universe.players[owner_id as usize].add_message(message.0, message.1);
+}
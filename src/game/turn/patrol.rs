@@ -0,0 +1,99 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::ShipOrderType;
+use ::game::objects::fleet::Waypoint;
+use ::game::objects::fleet::WaypointTarget;
+use ::game::objects::player::PlayerRelation;
+
+/*
+    Sends patrolling fleets after the nearest enemy fleet they can see.
+
+    A fleet patrols while it sits at a patrol waypoint with no further
+    orders, or while it is already chasing a fleet for a patrol.  Each year
+    it picks the closest visible fleet of a player it considers an enemy
+    within its patrol range and heads for it.  The intercept waypoint keeps
+    the patrol task, so the fleet follows its target as it moves and goes
+    on patrolling wherever the chase ends.
+*/
+pub fn patrol_fleets(universe: &mut Universe) {
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        let (range, warp) = match patrol_settings(universe, fid) {
+            Some(p) => p,
+            None => continue
+        };
+
+        if let Some(target_id) = find_patrol_target(universe, fid, range) {
+            let location = universe.fleets[&target_id].location.clone();
+            let f = universe.fleets.get_mut(&fid).unwrap();
+
+            f.orders.truncate(1);
+            f.orders.push(Waypoint::construct(WaypointTarget::Fleet(target_id), location, warp, ShipOrderType::Patrol { range, warp }));
+            f.repeat_orders = false;
+        }
+    }
+}
+
+fn patrol_settings(universe: &Universe, fleet_id: u32) -> Option<(u16, u8)> {
+    let f = universe.fleets.get(&fleet_id)?;
+
+    match f.orders.get(1) {
+        None => match f.orders[0].task {
+            ShipOrderType::Patrol { range, warp } if f.location.is_same_location(&f.orders[0].location) => Some((range, warp)),
+            _ => None
+        },
+        Some(w) => match (&w.target, &w.task) {
+            (&WaypointTarget::Fleet(_), &ShipOrderType::Patrol { range, warp }) if f.orders.len() == 2 => Some((range, warp)),
+            _ => None
+        }
+    }
+}
+
+fn find_patrol_target(universe: &Universe, fleet_id: u32, range: u16) -> Option<u32> {
+    let f = &universe.fleets[&fleet_id];
+    let owner_id = f.owner_id?;
+
+    let limit = if range == 0 { universe.fleet_scanner_range(fleet_id) } else { range } as f64;
+    let player = &universe.players[owner_id as usize];
+
+    let mut best : Option<(f64, u32)> = None;
+    for (tid, target) in universe.fleets.iter() {
+        let enemy = match target.owner_id {
+            Some(o) => player.relation_to(o) == PlayerRelation::Enemy,
+            None => false
+        };
+
+        if !enemy || !universe.is_fleet_visible_to(owner_id, *tid) {
+            continue;
+        }
+
+        let distance = f.location.distance_to(&target.location);
+        if distance <= limit && best.map_or(true, |(d, id)| distance < d || (distance == d && *tid < id)) {
+            best = Some((distance, *tid));
+        }
+    }
+
+    best.map(|(_, id)| id)
+}
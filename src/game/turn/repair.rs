@@ -0,0 +1,144 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::player::PlayerRelation;
+
+// Percent of each ship's armor repaired per year
+pub const REPAIR_PERCENT_MOVING : u32 = 1;
+pub const REPAIR_PERCENT_IN_SPACE : u32 = 2;
+pub const REPAIR_PERCENT_IN_ORBIT : u32 = 3;
+pub const REPAIR_PERCENT_OWNED_PLANET : u32 = 5;
+pub const REPAIR_PERCENT_STARBASE : u32 = 20;
+
+// Percent of its own armor a starbase repairs per year
+pub const STARBASE_SELF_REPAIR_PERCENT : u32 = 10;
+
+/*
+    Repairs and refuels fleets at the end of the year.
+
+    Fleets repair a share of their armor depending on where they are:
+
+        moving                          1%
+        stopped in deep space           2%
+        orbiting a planet               3%
+        orbiting their own planet       5%
+        at a friendly starbase w/ dock  20%
+
+    A fuel transport hull in the fleet adds its bonus to the rate.  Fleets
+    orbiting a friendly starbase are also refueled.
+*/
+pub fn repair_fleets(universe: &mut Universe) {
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        let percent = calculate_repair_percent(universe, fid);
+        let refuel = at_friendly_starbase(universe, fid).is_some();
+
+        let repairs : Vec<u32> = universe.fleets[&fid].members.iter()
+            .map(|m| {
+                let armor = universe.lookup_ship_design(m.design_id).map_or(0, |d| d.calculate_armor());
+                let amount = (armor * m.quantity as u32 * percent).div_ceil(100);
+                amount.min(m.damage)
+            })
+            .collect();
+
+        let f = universe.fleets.get_mut(&fid).unwrap();
+        for (m, repaired) in f.members.iter_mut().zip(repairs) {
+            m.damage -= repaired;
+        }
+
+        if refuel {
+            f.current_fuel = f.total_fuel_capacity;
+        }
+    }
+}
+
+fn calculate_repair_percent(universe: &Universe, fleet_id: u32) -> u32 {
+    let f = &universe.fleets[&fleet_id];
+
+    let moving = match f.next_waypoint() {
+        Some(w) => w.warp > 0 && !f.is_holding_for_repair(),
+        None => false
+    };
+
+    let base = if moving {
+        REPAIR_PERCENT_MOVING
+    }
+    else if at_friendly_starbase(universe, fleet_id) == Some(true) {
+        REPAIR_PERCENT_STARBASE
+    }
+    else {
+        match universe.planets.iter().find(|p| p.location.is_same_location(&f.location)) {
+            Some(p) if p.owner_id.is_some() && p.owner_id == f.owner_id => REPAIR_PERCENT_OWNED_PLANET,
+            Some(_) => REPAIR_PERCENT_IN_ORBIT,
+            None => REPAIR_PERCENT_IN_SPACE
+        }
+    };
+
+    let bonus = f.members.iter()
+        .filter_map(|m| universe.lookup_ship_design(m.design_id))
+        .map(|d| d.regen_bonus_percent())
+        .max()
+        .unwrap_or(0);
+
+    base + bonus
+}
+
+/*
+    Whether the fleet orbits a starbase owned by its player or by someone
+    who counts that player as a friend.  The value tells if the starbase has
+    a dock.
+*/
+fn at_friendly_starbase(universe: &Universe, fleet_id: u32) -> Option<bool> {
+    let f = &universe.fleets[&fleet_id];
+    let owner_id = f.owner_id?;
+
+    let planet = universe.planets.iter().find(|p| p.location.is_same_location(&f.location))?;
+    let starbase = universe.get_planet_starbase(planet.id)?;
+
+    if universe.players[starbase.owner_id as usize].relation_to(owner_id) != PlayerRelation::Friend {
+        return None;
+    }
+
+    universe.lookup_ship_design(starbase.design_id).map(|d| d.dock_capacity().is_some())
+}
+
+/*
+    Starbases repair a share of their own armor every year, whether or not
+    they fought.
+*/
+pub fn repair_starbases(universe: &mut Universe) {
+    let repairs : Vec<(u32, u32)> = universe.starbases.values()
+        .filter(|sb| sb.damage > 0)
+        .map(|sb| {
+            let armor = universe.lookup_ship_design(sb.design_id).map_or(0, |d| d.calculate_armor());
+            (sb.id, (armor * STARBASE_SELF_REPAIR_PERCENT).div_ceil(100))
+        })
+        .collect();
+
+    for (sid, amount) in repairs {
+        if let Some(sb) = universe.starbases.get_mut(&sid) {
+            sb.damage = sb.damage.saturating_sub(amount);
+        }
+    }
+}
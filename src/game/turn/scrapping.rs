@@ -0,0 +1,148 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::MineralContents;
+use ::game::objects::race::LesserRacialTrait;
+use ::game::objects::message::MessageId;
+
+// Percent of a ship's minerals recovered when scrapped at a planet, or at a
+// planet with one of the owner's starbases (Stars! help, "Scrapping a
+// fleet").  Salvage left in deep space holds what a planet would recover.
+pub const SCRAP_AT_PLANET_PERCENT : u32 = 33;
+pub const SCRAP_AT_STARBASE_PERCENT : u32 = 80;
+
+// Ultimate Recycling recovers more minerals and also some resources
+// (Stars! help, "Lesser Racial Traits")
+pub const ULTIMATE_RECYCLING_AT_PLANET_PERCENT : u32 = 45;
+pub const ULTIMATE_RECYCLING_AT_STARBASE_PERCENT : u32 = 90;
+pub const ULTIMATE_RECYCLING_RESOURCES_AT_PLANET_PERCENT : u32 = 35;
+pub const ULTIMATE_RECYCLING_RESOURCES_AT_STARBASE_PERCENT : u32 = 70;
+
+/*
+    Dismantles a fleet.  Part of the ships' minerals are recovered, more if
+    the owner has a starbase at the planet, and all of the cargo minerals
+    are dropped along with them.  In deep space the minerals are left behind
+    as salvage.  Ultimate Recycling races recover extra minerals and, at
+    their own planets, also turn part of the ships' cost into resources for
+    immediate use.  With nothing to build yet, those resources go to the
+    owner's current research field.
+*/
+pub fn scrap_fleet(universe: &mut Universe, fleet_id: u32) {
+    let (owner_id, location) = match universe.fleets.get(&fleet_id) {
+        Some(f) => (f.owner_id, f.location.clone()),
+        None => return
+    };
+
+    let mut minerals = (0, 0, 0);
+    let mut resources = 0;
+    for m in universe.fleets[&fleet_id].members.iter() {
+        if let Some(d) = universe.lookup_ship_design(m.design_id) {
+            let cost = d.calculate_cost();
+            let quantity = m.quantity as u32;
+            minerals.0 += cost.ironium * quantity;
+            minerals.1 += cost.boranium * quantity;
+            minerals.2 += cost.germanium * quantity;
+            resources += cost.resources * quantity;
+        }
+    }
+
+    let ultimate_recycling = match owner_id {
+        Some(pid) => universe.players[pid as usize].race.lesser_racial_traits.contains(&LesserRacialTrait::UltimateRecycling),
+        None => false
+    };
+
+    let planet_index = universe.planets.iter().position(|p| p.location.is_same_location(&location));
+    let at_starbase = match planet_index {
+        Some(i) => {
+            let planet_id = universe.planets[i].id;
+            match universe.get_planet_starbase(planet_id) {
+                Some(sb) => Some(sb.owner_id) == owner_id,
+                None => false
+            }
+        },
+        None => false
+    };
+
+    let fleet = universe.remove_fleet(fleet_id).unwrap();
+
+    let mineral_percent = match (at_starbase, ultimate_recycling) {
+        (true, true) => ULTIMATE_RECYCLING_AT_STARBASE_PERCENT,
+        (true, false) => SCRAP_AT_STARBASE_PERCENT,
+        (false, true) => ULTIMATE_RECYCLING_AT_PLANET_PERCENT,
+        (false, false) => SCRAP_AT_PLANET_PERCENT
+    };
+
+    let ironium = minerals.0 * mineral_percent / 100 + fleet.cargo.ironium;
+    let boranium = minerals.1 * mineral_percent / 100 + fleet.cargo.boranium;
+    let germanium = minerals.2 * mineral_percent / 100 + fleet.cargo.germanium;
+    let total = ironium + boranium + germanium;
+
+    let (message_id, parameters) = match planet_index {
+        Some(i) => {
+            let planet = &mut universe.planets[i];
+            planet.on_surface.add_saturating(ironium, boranium, germanium);
+
+            let mut bonus = 0;
+            if ultimate_recycling && planet.owner_id == owner_id {
+                let resource_percent = if at_starbase {
+                    ULTIMATE_RECYCLING_RESOURCES_AT_STARBASE_PERCENT
+                }
+                else {
+                    ULTIMATE_RECYCLING_RESOURCES_AT_PLANET_PERCENT
+                };
+
+                bonus = resources * resource_percent / 100;
+                if let Some(pid) = owner_id {
+                    let player = &mut universe.players[pid as usize];
+                    player.tech_progress[player.current_research_field.value()] += bonus;
+                }
+            }
+
+            let message_id = match (at_starbase, bonus > 0) {
+                (true, true) => MessageId::ScrappedAtStarbaseRecycled,
+                (true, false) => MessageId::ScrappedAtStarbase,
+                (false, true) => MessageId::ScrappedAtPlanetRecycled,
+                (false, false) => MessageId::ScrappedAtPlanet
+            };
+
+            let mut parameters = vec![fleet.name.clone(), total.to_string(), planet.name.clone()];
+            if bonus > 0 {
+                parameters.push(bonus.to_string());
+            }
+
+            (message_id, parameters)
+        },
+        None => {
+            universe.add_salvage(location, MineralContents {
+                ironium: ironium.min(u16::MAX as u32) as u16,
+                boranium: boranium.min(u16::MAX as u32) as u16,
+                germanium: germanium.min(u16::MAX as u32) as u16
+            });
+
+            (MessageId::ScrappedInSpace, vec![fleet.name.clone()])
+        }
+    };
+
+    if let Some(pid) = owner_id {
+        universe.players[pid as usize].add_message(message_id, parameters);
+    }
+}
@@ -0,0 +1,396 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::fleet::WaypointTarget;
+use ::game::objects::fleet::Cargo;
+use ::game::objects::fleet::COLONISTS_PER_KT;
+use ::game::objects::tech::STARGATE_INFINITE_VALUE;
+use ::game::objects::player::PlayerRelation;
+use ::game::objects::message::MessageId;
+use ::game::turn::movement::update_orbits;
+
+// Gates refuse jumps beyond this many times their safe mass or distance
+pub const STARGATE_MAX_OVERLOAD : f64 = 5.0;
+
+/*
+    Chance of losing a ship when a limit is exceeded.  It grows evenly
+    from nothing at the safe limit to certain loss at five times it.
+*/
+fn overload_risk(value: f64, safe: u16) -> f64 {
+    if safe == STARGATE_INFINITE_VALUE {
+        return 0.0;
+    }
+
+    ((value / safe as f64 - 1.0) / (STARGATE_MAX_OVERLOAD - 1.0)).clamp(0.0, 1.0)
+}
+
+// The tighter of the two gates' limits, where 0 means unlimited
+fn tighter_limit(a: u16, b: u16) -> u16 {
+    match (a, b) {
+        (STARGATE_INFINITE_VALUE, _) => b,
+        (_, STARGATE_INFINITE_VALUE) => a,
+        _ => a.min(b)
+    }
+}
+
+fn exceeds_overload(value: f64, safe: u16) -> bool {
+    safe != STARGATE_INFINITE_VALUE && value > safe as f64 * STARGATE_MAX_OVERLOAD
+}
+
+/*
+    Jumps a fleet through the stargate on the planet it orbits to the
+    stargate at its next waypoint, arriving there this year.
+
+    Both planets need a starbase with a stargate, owned by the fleet's
+    owner or a friend.  The jump uses the smaller safe mass and safe
+    distance of the two gates.  Ships heavier than the safe mass, or any
+    jump further than the safe distance, risk losing each ship; gates
+    refuse ships and jumps more than five times over their limits.
+
+    Cargo is left behind on the planet before the jump.  Colonists can only
+    be dropped off on the owner's own planets, so a fleet carrying them
+    cannot jump from anywhere else.  Returns whether the fleet arrived.
+*/
+pub fn use_stargate(universe: &mut Universe, rng: &mut StdRng, fleet_id: u32) -> bool {
+    let (owner_id, name, location, target, destination, cargo) = match universe.fleets.get(&fleet_id) {
+        Some(f) if !f.is_holding_for_repair() => match (f.owner_id, f.next_waypoint()) {
+            (Some(o), Some(w)) => (o, f.name.clone(), f.location.clone(), w.target.clone(), w.location.clone(), f.cargo.clone()),
+            _ => return false
+        },
+        _ => return false
+    };
+
+    let friendly = |universe: &Universe, planet_id: u32| -> bool {
+        match universe.get_planet_starbase(planet_id) {
+            Some(sb) => universe.players[sb.owner_id as usize].relation_to(owner_id) == PlayerRelation::Friend,
+            None => false
+        }
+    };
+
+    let source = universe.planets.iter().find(|p| p.location.is_same_location(&location)).map(|p| (p.id, p.name.clone(), p.owner_id));
+    let (source_id, source_name, source_owner) = match source {
+        Some(s) if universe.planet_stargate(s.0).is_some() => s,
+        _ => {
+            let where_text = universe.describe_location(&location);
+            universe.players[owner_id as usize].add_message(MessageId::StargateNotAtSource, vec![name, where_text]);
+            return false;
+        }
+    };
+
+    if !friendly(universe, source_id) {
+        universe.players[owner_id as usize].add_message(MessageId::StargateSourceNotFriendly, vec![name, source_name.clone(), source_name]);
+        return false;
+    }
+
+    let destination_id = match target {
+        WaypointTarget::Planet(pid) => pid,
+        _ => {
+            let where_text = universe.describe_location(&destination);
+            universe.players[owner_id as usize].add_message(MessageId::StargateNoDestinationPlanet, vec![name, where_text]);
+            return false;
+        }
+    };
+
+    let destination_name = universe.get_planet(destination_id).map_or(String::new(), |p| p.name.clone());
+    let destination_gate = match universe.planet_stargate(destination_id) {
+        Some(g) => g,
+        None => {
+            universe.players[owner_id as usize].add_message(MessageId::StargateNotAtDestination, vec![name, source_name, destination_name]);
+            return false;
+        }
+    };
+
+    if !friendly(universe, destination_id) {
+        universe.players[owner_id as usize].add_message(MessageId::StargateDestinationBlocked, vec![name, source_name, destination_name.clone(), destination_name]);
+        return false;
+    }
+
+    let source_gate = universe.planet_stargate(source_id).unwrap();
+    let safe_mass = tighter_limit(source_gate.0, destination_gate.0);
+    let safe_distance = tighter_limit(source_gate.1, destination_gate.1);
+    let distance = location.distance_to(&destination);
+
+    if exceeds_overload(distance, safe_distance) {
+        universe.players[owner_id as usize].add_message(MessageId::StargateOutOfRange, vec![name, source_name, destination_name]);
+        return false;
+    }
+
+    // Mass of one ship of each stack in the fleet, None if its design is
+    // unknown
+    let masses : Vec<Option<(String, f64)>> = universe.fleets[&fleet_id].members.iter()
+        .map(|m| universe.lookup_ship_design(m.design_id).map(|d| (d.name.clone(), d.calculate_mass() as f64)))
+        .collect();
+
+    if let Some(heavy) = masses.iter().flatten().find(|m| exceeds_overload(m.1, safe_mass)) {
+        let design_name = heavy.0.clone();
+        universe.players[owner_id as usize].add_message(MessageId::StargateTooMassive, vec![name, source_name, destination_name, design_name]);
+        return false;
+    }
+
+    if cargo.colonists > 0 && source_owner != Some(owner_id) {
+        universe.players[owner_id as usize].add_message(MessageId::StargateColonistsOnBoard, vec![name, source_name]);
+        return false;
+    }
+
+    unload_before_jump(universe, fleet_id, source_id);
+
+    let distance_risk = overload_risk(distance, safe_distance);
+    let mut lost_total = 0;
+    {
+        let f = universe.fleets.get_mut(&fleet_id).unwrap();
+        for (m, mass) in f.members.iter_mut().zip(masses.iter()) {
+            let mass_risk = mass.as_ref().map_or(0.0, |&(_, mass)| overload_risk(mass, safe_mass));
+            let risk = 1.0 - (1.0 - mass_risk) * (1.0 - distance_risk);
+            if risk == 0.0 {
+                continue;
+            }
+
+            let lost = (0..m.quantity).filter(|_| rng.gen::<f64>() < risk).count() as u16;
+            if lost > 0 {
+                m.damage = m.damage * (m.quantity - lost) as u32 / m.quantity as u32;
+                m.quantity -= lost;
+                lost_total += lost as u32;
+            }
+        }
+        f.members.retain(|m| m.quantity > 0);
+    }
+
+    let ships = universe.fleets[&fleet_id].ship_count();
+    if ships == 0 {
+        universe.remove_fleet(fleet_id);
+        universe.players[owner_id as usize].add_message(MessageId::StargateFleetLost, vec![name, source_name, destination_name]);
+        return false;
+    }
+
+    if lost_total > 0 {
+        let share = lost_total as f64 / (lost_total + ships) as f64;
+        let message_id = if share <= 0.25 {
+            MessageId::StargateFewShipsLost
+        }
+        else if share <= 0.5 {
+            MessageId::StargateShipsLost
+        }
+        else if share <= 0.75 {
+            MessageId::StargateManyShipsLost
+        }
+        else {
+            MessageId::StargateMostShipsLost
+        };
+
+        universe.players[owner_id as usize].add_message(message_id, vec![name, source_name, destination_name, lost_total.to_string()]);
+        universe.recalculate_fleet_capacities(fleet_id);
+    }
+
+    update_orbits(universe, fleet_id, &location, &destination);
+
+    let f = universe.fleets.get_mut(&fleet_id).unwrap();
+    f.location = destination;
+    f.advance_waypoint();
+    true
+}
+
+/*
+    Leaves everything the fleet carries on the planet it is jumping from.
+*/
+fn unload_before_jump(universe: &mut Universe, fleet_id: u32, planet_id: u32) {
+    let (owner_id, name, cargo) = {
+        let f = universe.fleets.get_mut(&fleet_id).unwrap();
+        let cargo = f.cargo.clone();
+        f.cargo = Cargo::default();
+        (f.owner_id.unwrap(), f.name.clone(), cargo)
+    };
+
+    let minerals = cargo.ironium + cargo.boranium + cargo.germanium;
+    let colonists = cargo.colonists * COLONISTS_PER_KT;
+
+    let planet_name = {
+        let p = universe.get_planet_mut(planet_id).unwrap();
+        p.on_surface.add_saturating(cargo.ironium, cargo.boranium, cargo.germanium);
+        p.population += colonists;
+        p.name.clone()
+    };
+
+    let message = match (minerals > 0, colonists > 0) {
+        (true, true) => Some((MessageId::StargateUnloadedColonistsAndMinerals, vec![name, colonists.to_string(), minerals.to_string(), planet_name])),
+        (true, false) => Some((MessageId::StargateUnloadedMinerals, vec![name, minerals.to_string(), planet_name])),
+        (false, true) => Some((MessageId::StargateUnloadedColonists, vec![name, colonists.to_string(), planet_name])),
+        (false, false) => None
+    };
+
+    if let Some((message_id, parameters)) = message {
+        universe.players[owner_id as usize].add_message(message_id, parameters);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use ::game::objects::player::Player;
+    use ::game::objects::fleet::ShipDesign;
+    use ::game::objects::fleet::ShipSlot;
+    use ::game::objects::fleet::Waypoint;
+    use ::game::objects::fleet::ShipOrderType;
+    use ::game::objects::tech::TechnologyId;
+    use ::game::objects::universe::SpaceCoordinate;
+    use ::game::turn::movement::STARGATE_WARP;
+    use ::game::objects::predefined::races::create_humanoid;
+    use ::game::tutorial::generate_tutorial_universe;
+
+    fn design(hull: TechnologyId, parts: &[(usize, TechnologyId, u8)]) -> ShipDesign {
+        let mut slots : [Option<ShipSlot>; 16] = Default::default();
+        for &(slot, tid, amount) in parts.iter() {
+            slots[slot] = Some(ShipSlot { tid, amount });
+        }
+
+        ShipDesign {
+            id: 0,
+            icon_index: 0,
+            name: "Test".to_string(),
+            base_hull: hull,
+            is_obsolete: false,
+            slots: Some(slots)
+        }
+    }
+
+    /*
+        A Humanoid player owning the tutorial map's first two planets, the
+        given distance apart, each with a 100/250 stargate.  The player has
+        a fleet of the given design orbiting the first, ordered through the
+        gate to the second.
+    */
+    fn gate_universe(distance: u16, ship: ShipDesign, quantity: u16) -> (Universe, u32) {
+        let mut u = generate_tutorial_universe();
+        let mut player = Player::construct_from_race(create_humanoid());
+        player.id = 0;
+        u.players.push(player);
+
+        let index = u.players[0].add_starbase_design(design(TechnologyId::SpaceStation, &[(10, TechnologyId::Stargate100_250, 1)]));
+        let gate = u.players[0].starbase_designs[index as usize].clone().unwrap();
+
+        for (i, x) in [(0, 1000), (1, 1000 + distance)].iter() {
+            let planet_id = u.planets[*i].id;
+            u.planets[*i].location = SpaceCoordinate { x: *x, y: 1000 };
+            u.planets[*i].owner_id = Some(0);
+            u.add_starbase_at_planet(&gate, 0, planet_id);
+        }
+
+        let index = u.players[0].add_ship_design(ship);
+        let ship = u.players[0].ship_designs[index as usize].clone().unwrap();
+        let fid = u.add_fleet_at_planet(&ship, Some(0), u.planets[0].id, quantity);
+
+        let target = WaypointTarget::Planet(u.planets[1].id);
+        let destination = u.planets[1].location.clone();
+        u.fleets.get_mut(&fid).unwrap().orders.push(Waypoint::construct(target, destination, STARGATE_WARP, ShipOrderType::NoTask));
+        (u, fid)
+    }
+
+    fn message_ids(u: &Universe) -> Vec<u16> {
+        u.players[0].messages.iter().map(|m| m.message_id).collect()
+    }
+
+    #[test]
+    fn overload_risk_grows_from_the_safe_limit_to_five_times_it() {
+        assert_eq!(overload_risk(100.0, 100), 0.0);
+        assert_eq!(overload_risk(300.0, 100), 0.5);
+        assert_eq!(overload_risk(500.0, 100), 1.0);
+        assert_eq!(overload_risk(10000.0, STARGATE_INFINITE_VALUE), 0.0);
+
+        assert!(!exceeds_overload(500.0, 100));
+        assert!(exceeds_overload(501.0, 100));
+        assert!(!exceeds_overload(10000.0, STARGATE_INFINITE_VALUE));
+    }
+
+    #[test]
+    fn the_tighter_gate_limit_applies_and_unlimited_gates_defer() {
+        assert_eq!(tighter_limit(100, 300), 100);
+        assert_eq!(tighter_limit(STARGATE_INFINITE_VALUE, 300), 300);
+        assert_eq!(tighter_limit(100, STARGATE_INFINITE_VALUE), 100);
+        assert_eq!(tighter_limit(STARGATE_INFINITE_VALUE, STARGATE_INFINITE_VALUE), STARGATE_INFINITE_VALUE);
+    }
+
+    #[test]
+    fn jumps_within_the_limits_arrive_intact() {
+        let (mut u, fid) = gate_universe(250, design(TechnologyId::Scout, &[]), 10);
+
+        assert!(use_stargate(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        assert!(u.fleets[&fid].location.is_same_location(&u.planets[1].location));
+        assert_eq!(u.fleets[&fid].ship_count(), 10);
+        assert!(message_ids(&u).is_empty());
+    }
+
+    #[test]
+    fn gates_refuse_jumps_beyond_five_times_their_range() {
+        let (mut u, fid) = gate_universe(1251, design(TechnologyId::Scout, &[]), 1);
+
+        assert!(!use_stargate(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        assert!(u.fleets[&fid].location.is_same_location(&u.planets[0].location));
+        assert_eq!(message_ids(&u), vec![MessageId::StargateOutOfRange as u16]);
+    }
+
+    #[test]
+    fn gates_refuse_ships_beyond_five_times_their_safe_mass() {
+        let heavy = design(TechnologyId::Dreadnought, &[(5, TechnologyId::Tritanium, 5)]);
+        assert!(heavy.calculate_mass() > 500);
+        let (mut u, fid) = gate_universe(100, heavy, 1);
+
+        assert!(!use_stargate(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        assert_eq!(message_ids(&u), vec![MessageId::StargateTooMassive as u16]);
+    }
+
+    #[test]
+    fn overloaded_jumps_lose_ships() {
+        // 250kg through a 100kg gate: each ship has a 37.5% chance of loss
+        let (mut u, fid) = gate_universe(100, design(TechnologyId::Dreadnought, &[]), 20);
+
+        assert!(use_stargate(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        let ships = u.fleets[&fid].ship_count();
+        assert!(ships > 0 && ships < 20);
+        assert_eq!(u.players[0].messages.last().unwrap().parameters[3], (20 - ships).to_string());
+    }
+
+    #[test]
+    fn cargo_is_left_behind_before_the_jump() {
+        let (mut u, fid) = gate_universe(100, design(TechnologyId::Scout, &[]), 1);
+        u.fleets.get_mut(&fid).unwrap().cargo.ironium = 10;
+        let ironium = u.planets[0].on_surface.ironium;
+
+        assert!(use_stargate(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        assert_eq!(u.fleets[&fid].cargo.ironium, 0);
+        assert_eq!(u.planets[0].on_surface.ironium, ironium + 10);
+        assert_eq!(message_ids(&u), vec![MessageId::StargateUnloadedMinerals as u16]);
+    }
+
+    #[test]
+    fn fleets_cannot_jump_colonists_from_a_planet_they_do_not_own() {
+        let (mut u, fid) = gate_universe(100, design(TechnologyId::Scout, &[]), 1);
+        u.fleets.get_mut(&fid).unwrap().cargo.colonists = 10;
+        u.planets[0].owner_id = None;
+
+        assert!(!use_stargate(&mut u, &mut StdRng::seed_from_u64(1), fid));
+        assert_eq!(u.fleets[&fid].cargo.colonists, 10);
+        assert_eq!(message_ids(&u), vec![MessageId::StargateColonistsOnBoard as u16]);
+    }
+}
@@ -0,0 +1,171 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::universe::Heading;
+use ::game::objects::fleet::WaypointTarget;
+use ::game::objects::game::STARTING_YEAR;
+use ::game::objects::tech::TechnologyId;
+use ::game::objects::tech::MAX_TECH_LEVEL;
+use ::game::objects::tech::MYSTERY_TRADER_TECHNOLOGY;
+use ::game::objects::trader::MysteryTrader;
+use ::game::objects::trader::TraderItemKind;
+use ::game::objects::trader::trader_item_kind;
+use ::game::objects::trader::MYSTERY_TRADER_INTERVAL;
+use ::game::objects::trader::MYSTERY_TRADER_MIN_MINERALS;
+use ::game::objects::trader::MYSTERY_TRADER_STOCK;
+use ::game::objects::trader::MYSTERY_TRADER_MAX_TECH_ADVANCES;
+use ::game::objects::trader::MYSTERY_TRADER_MIN_WARP;
+use ::game::objects::trader::MYSTERY_TRADER_MAX_WARP;
+use ::game::objects::message::MessageId;
+use ::game::turn::movement::warp_distance;
+
+/*
+    Moves each Mystery Trader along its course; traders that reach the far
+    edge of the universe leave.  Every 25 years a new trader enters from a
+    random point on one edge, heading for the opposite edge with a few of
+    the special items for sale, and every player is told of its arrival.
+*/
+pub fn move_mystery_traders(universe: &mut Universe, rng: &mut StdRng, year: u32) {
+    for t in universe.mystery_traders.iter_mut() {
+        t.location = t.location.step_toward(&t.heading.next_location, warp_distance(t.heading.warp));
+    }
+    universe.mystery_traders.retain(|t| !t.location.is_same_location(&t.heading.next_location));
+
+    if year <= STARTING_YEAR || (year - STARTING_YEAR) % MYSTERY_TRADER_INTERVAL != 0 {
+        return;
+    }
+
+    let dimension = universe.boundary.iter().map(|c| c.x.max(c.y)).max().unwrap_or(0) as u32;
+    let (a, b) = (rng.gen_range(0, dimension + 1) as u16, rng.gen_range(0, dimension + 1) as u16);
+    let far = dimension as u16;
+
+    let (mut start, mut end) = if rng.gen_bool(0.5) {
+        (SpaceCoordinate { x: 0, y: a }, SpaceCoordinate { x: far, y: b })
+    }
+    else {
+        (SpaceCoordinate { x: a, y: 0 }, SpaceCoordinate { x: b, y: far })
+    };
+    if rng.gen_bool(0.5) {
+        ::std::mem::swap(&mut start, &mut end);
+    }
+
+    let mut items : Vec<TechnologyId> = MYSTERY_TRADER_TECHNOLOGY.to_vec();
+    items.shuffle(rng);
+    items.truncate(MYSTERY_TRADER_STOCK);
+
+    let id = universe.mystery_traders.iter().map(|t| t.id + 1).max().unwrap_or(0);
+    universe.mystery_traders.push(MysteryTrader {
+        id,
+        location: start,
+        heading: Heading { warp: rng.gen_range(MYSTERY_TRADER_MIN_WARP, MYSTERY_TRADER_MAX_WARP + 1), next_location: end },
+        items,
+        customers: Vec::new()
+    });
+
+    for player in universe.players.iter_mut() {
+        player.add_message(MessageId::MysteryTraderArrived, Vec::new());
+    }
+}
+
+/*
+    Fleets that reach a Mystery Trader they were sent to meet offer it
+    their minerals.  A fleet carrying at least 5000kT is absorbed by the
+    trader in exchange for one of its items that the player does not
+    already have, or failing that, a tech level in a random field for
+    every 5000kT (up to three).  Each player may only trade once with each
+    trader.
+*/
+pub fn trade_with_mystery_traders(universe: &mut Universe, rng: &mut StdRng) {
+    let mut fleet_ids : Vec<u32> = universe.fleets.keys().cloned().collect();
+    fleet_ids.sort();
+
+    for fid in fleet_ids {
+        let (owner_id, name, trader_id, minerals) = match universe.fleets.get(&fid) {
+            Some(f) => match (f.owner_id, &f.orders[0].target) {
+                (Some(o), &WaypointTarget::MysteryTrader(tid)) => (o, f.name.clone(), tid, f.cargo.ironium + f.cargo.boranium + f.cargo.germanium),
+                _ => continue
+            },
+            None => continue
+        };
+
+        let index = match universe.mystery_traders.iter().position(|t| t.id == trader_id && t.location.is_same_location(&universe.fleets[&fid].location)) {
+            Some(i) => i,
+            None => continue
+        };
+
+        if universe.mystery_traders[index].customers.contains(&owner_id) {
+            universe.players[owner_id as usize].add_message(MessageId::MysteryTraderRecovering, vec![name]);
+            continue;
+        }
+
+        if minerals < MYSTERY_TRADER_MIN_MINERALS {
+            universe.players[owner_id as usize].add_message(MessageId::MysteryTraderRefused, vec![name]);
+            continue;
+        }
+
+        universe.remove_fleet(fid);
+        universe.mystery_traders[index].customers.push(owner_id);
+
+        let item = universe.mystery_traders[index].items.iter()
+            .find(|tid| !universe.players[owner_id as usize].trader_items.contains(tid))
+            .cloned();
+
+        let player = &mut universe.players[owner_id as usize];
+        let message = match item {
+            Some(tid) => {
+                player.trader_items.push(tid);
+                match trader_item_kind(tid) {
+                    TraderItemKind::Part => (MessageId::MysteryTraderPart, vec![name]),
+                    TraderItemKind::Hull => (MessageId::MysteryTraderHull, vec![name]),
+                    TraderItemKind::PlanetaryDevice => (MessageId::MysteryTraderDevice, vec![name])
+                }
+            },
+            None => {
+                let mut advances = 0;
+                for _ in 0..(minerals / MYSTERY_TRADER_MIN_MINERALS).min(MYSTERY_TRADER_MAX_TECH_ADVANCES) {
+                    let fields : Vec<usize> = (0..player.tech_level.len())
+                        .filter(|f| player.tech_level[*f] < MAX_TECH_LEVEL)
+                        .collect();
+
+                    if let Some(field) = fields.choose(rng) {
+                        player.advance_tech_level(*field);
+                        advances += 1;
+                    }
+                }
+
+                if advances > 0 {
+                    (MessageId::MysteryTraderTechAdvances, vec![name, advances.to_string()])
+                }
+                else {
+                    (MessageId::MysteryTraderNothingNew, vec![name])
+                }
+            }
+        };
+
+        player.add_message(message.0, message.1);
+    }
+}
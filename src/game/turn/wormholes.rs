@@ -0,0 +1,51 @@
+/*
+ *  Copyright 2019 Brandon Arrendondo
+ *
+ *  Permission is hereby granted, free of charge, to any person obtaining a
+ *  copy of this software and associated documentation files (the "Software"),
+ *  to deal in the Software without restriction, including without limitation
+ *  the rights to use, copy, modify, merge, publish, distribute, sublicense,
+ *  and/or sell copies of the Software, and to permit persons to whom the
+ *  Software is furnished to do so, subject to the following conditions:
+ *
+ *  The above copyright notice and this permission notice shall be included in
+ *  all copies or substantial portions of the Software.
+ *
+ *  THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ *  IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ *  FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+ *  THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ *  LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+ *  FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+ *  DEALINGS IN THE SOFTWARE.
+ */
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use ::game::objects::universe::Universe;
+use ::game::objects::universe::SpaceCoordinate;
+use ::game::objects::universe::WormholeStability;
+
+/*
+    Wormholes drift to a new spot in the universe from time to time; the
+    less stable they are, the more often they move.  This happens every
+    year whether or not random events are allowed.
+*/
+pub fn shift_wormholes(universe: &mut Universe, rng: &mut StdRng) {
+    let dimension = universe.boundary.iter().map(|c| c.x.max(c.y)).max().unwrap_or(0);
+
+    for wh in universe.wormholes.iter_mut() {
+        let chance = match wh.stability {
+            WormholeStability::RockSolid => 0.0,
+            WormholeStability::Stable => 0.1,
+            WormholeStability::Volatile => 0.25
+        };
+
+        if rng.gen_bool(chance) {
+            wh.location = SpaceCoordinate {
+                x: rng.gen_range(0, dimension as u32 + 1) as u16,
+                y: rng.gen_range(0, dimension as u32 + 1) as u16
+            };
+        }
+    }
+}
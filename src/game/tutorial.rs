@@ -20,6 +20,7 @@
  *  DEALINGS IN THE SOFTWARE.
  */
 use uuid::Uuid;
+use rand;
 
 use ::game::objects::universe::Universe;
 use ::game::objects::universe::UniverseSize;
@@ -63,7 +64,6 @@ pub fn generate_tutorial_game() -> Game {
     u.planets[10].population = p2.race.calculate_starting_population(UniverseSize::Tiny);
 
     u.players.push(p2);
-    u.generate_initial_ships();
 
     let vc = VictoryConditions {
         owns_percent_planets: false,
@@ -98,12 +98,20 @@ pub fn generate_tutorial_game() -> Game {
         victory_conditions: vc
     };
 
+    u.generate_homeworld_installations(params.accelerated_play);
+    u.generate_initial_ships(params.accelerated_play);
+    u.generate_initial_starbases();
+    u.record_original_habitats();
+
     Game {
         id: gid,
         name: "Tutorial Game".to_string(),
         year: ::game::objects::game::STARTING_YEAR,
         parameters: params,
-        universe: u
+        universe: u,
+        save_version: ::game::objects::game::SAVE_VERSION,
+        random_seed: rand::random(),
+        battles: Vec::new()
     }
 }
 
@@ -118,10 +126,12 @@ pub fn generate_tutorial_universe() -> Universe {
         boundary: boundaries,
         minefields: Vec::new(),
         mineral_packets: Vec::new(),
+        mystery_traders: Vec::new(),
         salvage: Vec::new(),
         wormholes: Vec::new(),
         planets: Vec::new(),
         fleets: HashMap::new(),
+        starbases: HashMap::new(),
         players: Vec::new()
     };
 
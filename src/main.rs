@@ -66,6 +66,11 @@ pub mod game {
         pub mod tech;
         pub mod race;
         pub mod fleet;
+        pub mod starbase;
+        pub mod message;
+        pub mod order;
+        pub mod battle;
+        pub mod trader;
         pub mod predefined {
             pub mod races;
             pub mod messages;
@@ -73,6 +78,26 @@ pub mod game {
             pub mod fleets;
         }
     }
+    pub mod turn {
+        pub mod generation;
+        pub mod movement;
+        pub mod cargo;
+        pub mod colonization;
+        pub mod scrapping;
+        pub mod mining;
+        pub mod minefields;
+        pub mod repair;
+        pub mod patrol;
+        pub mod battle;
+        pub mod bombing;
+        pub mod defenses;
+        pub mod invasion;
+        pub mod packets;
+        pub mod stargates;
+        pub mod events;
+        pub mod trader;
+        pub mod wormholes;
+    }
     pub mod tutorial;
     pub mod db {
         pub mod storage;
@@ -150,6 +150,45 @@ fn get_planet_dump(query: &str) -> Response {
 
 
 
+/*
+    Returns the recording of a battle.  Battles are stored with the game as
+    of the year after they were fought.
+
+    There is no access control: the service does not authenticate players,
+    so anyone who knows the game id can fetch any battle.  The recording
+    lists its participants and observers for when players can be told apart.
+*/
+fn get_battle(query: &str) -> Response {
+    let args = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+
+    let year = args.get("year").and_then(|y| y.parse::<u32>().ok());
+    let battle_id = args.get("battle_id").and_then(|b| b.parse::<u32>().ok());
+
+    match (args.get("game_id"), year, battle_id) {
+        (Some(game_id), Some(year), Some(battle_id)) => {
+            let game = match ::game::db::retrieval::lookup_game(game_id, year) {
+                Some(g) => g,
+                None => return Response::new().with_status(StatusCode::NotFound)
+            };
+
+            match game.battles.iter().find(|b| b.id == battle_id) {
+                Some(battle) => {
+                    let payload = serde_json::to_string(&battle).unwrap();
+
+                    Response::new()
+                        .with_header(ContentLength(payload.len() as u64))
+                        .with_header(ContentType::json())
+                        .with_body(payload)
+                },
+                None => Response::new().with_status(StatusCode::NotFound)
+            }
+        },
+        _ => json_build_invalid_request_response()
+    }
+}
+
 impl Service for GameService {
     type Request = Request;
     type Response = Response;
@@ -196,6 +235,18 @@ impl Service for GameService {
 
                 Box::new(response)
             },
+            (&Get, "/battle") => {
+                let response = match request.query() {
+                    Some(query) => {
+                        futures::future::ok(get_battle(query))
+                    }
+                    None => {
+                        futures::future::ok(json_build_invalid_request_response())
+                    }
+                };
+
+                Box::new(response)
+            },
             (&Post, "/new_tutorial_game") => {
                 let future = request.body().concat2().and_then( |body| {
                     if json_request_is_valid(body, "/new_tutorial_game") {